@@ -1,6 +1,6 @@
 use cosmwasm_schema::write_api;
 
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use furya_nft_minter::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
 
 fn main() {
     write_api! {
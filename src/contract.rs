@@ -1,51 +1,269 @@
+use std::convert::TryFrom;
+
 use cosmwasm_std::{
-    Addr, Api, Binary, BlockInfo, ChannelResponse, CosmosMsg, Env, Response, StdError, StdResult,
-    Storage, Uint128, WasmMsg, to_binary, Querier
+    from_json, to_json_binary, to_json_vec, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut,
+    Env, MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, SubMsg, Uint128,
+    WasmMsg,
+};
+use cw20::{
+    Balance, BalanceResponse, Cw20Coin, Cw20CoinVerified, Cw20ExecuteMsg, Cw20QueryMsg,
+    Cw20ReceiveMsg,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use sha2::{Digest, Sha256};
 use crate::msg::Metadata;
-use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{Config, config, Whitelist, whitelist};
+use crate::merkle;
+use crate::msg::{
+    CheckRoyaltiesResponse, ConfigResponse, CurrentSupplyResponse, Cw721ExecuteMsg,
+    Cw721InstantiateMsg, ExecuteMsg, FulfillToken, InstantiateMsg, IsWhitelistedResponse,
+    MerkleProof, MigrateMsg, MintAllocationResponse, MintStatusResponse, MintVoucher,
+    PayeesResponse, PendingOwnerResponse, PhasesResponse, PlatformFeeResponse, PriceQuoteResponse,
+    QueryMsg, ReceiveMsg, RevenueResponse, RoyaltiesInfoResponse, TokenRequestByIndexResponse,
+    TokenRequestsCountResponse, VerifyMerkleProofResponse, VoucherUsageResponse,
+    WhitelistAllocation, WhitelistEntriesResponse, WhitelistEntryResponse, WhitelistSizeResponse,
+};
+use crate::state::{
+    Allowlist, Config, config, mint_counts, MintPhase, MintPhaseConfig, pending_token_requests,
+    phase_supply, phases, read_config, read_mint_counts, read_phase_supply, read_phases, read_pending_token_requests,
+    read_refunded_token_requests, read_supply, read_token_requests_count, read_token_requests_head,
+    refunded_token_requests, supply, TokenRequest, TokenRequestStatus, token_requests_count,
+    token_requests_head, state, read_state, LegacyConfig, read_legacy_config, whitelist, read_whitelist, whitelist_entries, whitelist_generation,
+    whitelist_size, WhitelistEntry, read_whitelist_entries, read_whitelist_generation,
+    read_whitelist_size, read_voucher_nonces, airdropped, read_airdropped, cw20_escrow, escrow,
+    Payment, read_cw20_escrow, read_escrow, read_revenue, revenue, auction_bids,
+    auction_clearing_price, BondingCurve, PriceMode, read_auction_bids,
+    read_auction_clearing_price, SignatureAlgorithm, voucher_nonces, VoucherSigner, Payee, payees,
+    read_payees, pending_owner, read_pending_owner, PendingOwner, read_token_royalties, token_royalties, Royalty, platform_fee, platform_fees, read_platform_fee, read_platform_fees, PlatformFee, platform_admin, read_platform_admin,
+};
+
+const CONTRACT_NAME: &str = "crates.io:furya-nft-minter";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Version record written by 1.0, which used the crate name rather than `CONTRACT_NAME`.
+const LEGACY_CONTRACT_NAME: &str = "furya_nft_minter";
+const LEGACY_VERSION: &str = "1.0";
+/// Legacy whitelist addresses moved by `migrate`; `MigrateWhitelist` moves the rest.
+pub(crate) const MIGRATE_WHITELIST_BATCH: usize = 50;
+
+/// Default lifetime of a token request before it can be cancelled, in seconds.
+const DEFAULT_REQUEST_EXPIRY: u64 = 7 * 24 * 60 * 60;
+
+const DEFAULT_MAX_PER_TX: u32 = 10;
+
+/// Prefix of the token ids `Airdrop` assigns. Other mints may not use it.
+const RESERVED_TOKEN_PREFIX: &str = "reserved-";
 
-pub fn init(
-    deps: &mut Extern<DefaultApi, Storage, Querier>,
+/// Reply id of the submessage instantiating the cw721 collection.
+pub const INSTANTIATE_NFT_REPLY_ID: u64 = 1;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+fn unauthorized() -> StdError {
+    StdError::generic_err("Unauthorized")
+}
+
+pub fn instantiate(
+    deps: DepsMut,
     env: Env,
+    _info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<InitResponse> {
+) -> StdResult<Response> {
     let config = Config {
-        minter: deps.api.addr_validate(&msg.minter)?,
-        nft_addr: msg.nft_addr,
+        minter: deps.api.addr_validate(&msg.minter)?.to_string(),
+        // Set once the collection has been instantiated, in `reply`.
+        nft_addr: Addr::unchecked(""),
         nft_base_uri: msg.nft_base_uri,
         nft_max_supply: msg.nft_max_supply,
         nft_price_amount: msg.nft_price_amount,
-        owner: deps.api.addr_validate(&msg.owner)?,
+        owner: deps.api.addr_validate(&msg.owner)?.to_string(),
         is_mintable: msg.is_mintable,
         mint_max: msg.mint_max,
         mint_start_time: msg.mint_start_time,
         nft_symbol: msg.nft_symbol,
         price_denom: msg.price_denom,
-        royalty_payment_address: msg.royalty_payment_address,
+        royalty_payment_address: deps.api.addr_validate(&msg.royalty_payment_address)?.to_string(),
         royalty_percentage: msg.royalty_percentage,
         whitelist_mint_max: msg.whitelist_mint_max,
         whitelist_mint_period: msg.whitelist_mint_period,
         whitelist_mint_price_amount: msg.whitelist_mint_price_amount,
         paused: false,
+        request_expiry: msg.request_expiry.unwrap_or(DEFAULT_REQUEST_EXPIRY),
+        closed: false,
+        voucher_signer: msg.voucher_signer,
+        max_per_tx: msg.max_per_tx.unwrap_or(DEFAULT_MAX_PER_TX),
+        reserved_supply: msg.reserved_supply.unwrap_or_default(),
+    };
+    check_royalty_percentage(config.royalty_percentage)?;
+    if config.reserved_supply > config.nft_max_supply {
+        return Err(StdError::generic_err("Reserved supply cannot exceed the max supply"));
+    }
+
+    let schedule = msg.phases.unwrap_or_else(|| config.default_phases());
+    validate_phases(&schedule)?;
+    let admin = msg
+        .platform_admin
+        .map(|admin| deps.api.addr_validate(&admin))
+        .transpose()?;
+    if let Some(fee) = msg.platform_fee {
+        if admin.is_none() {
+            return Err(StdError::generic_err("A platform fee needs a platform admin"));
+        }
+        let fee = validate_platform_fee(deps.as_ref(), &schedule, fee)?;
+        platform_fee(deps.storage).save(&fee)?;
+    }
+    if let Some(admin) = admin {
+        platform_admin(deps.storage).save(&admin)?;
+    }
+
+    config.save(deps.storage)?;
+    phases(deps.storage).save(&schedule)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let instantiate_nft = WasmMsg::Instantiate {
+        admin: Some(config.owner.to_string()),
+        code_id: msg.nft_ci,
+        msg: to_json_binary(&Cw721InstantiateMsg {
+            name: msg.nft_name.clone(),
+            symbol: config.nft_symbol.clone(),
+            minter: env.contract.address.to_string(),
+        })?,
+        funds: vec![],
+        label: msg.nft_name,
     };
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(instantiate_nft, INSTANTIATE_NFT_REPLY_ID))
+        .add_attribute("action", "instantiate"))
+}
+
+/// Upgrades the state of an older version of this contract in place.
+pub fn migrate(
+    deps: DepsMut,
+    env: Env,
+    _msg: MigrateMsg,
+) -> StdResult<Response> {
+    let stored = get_contract_version(deps.storage)?;
+    let legacy = stored.contract == LEGACY_CONTRACT_NAME && stored.version == LEGACY_VERSION;
+    if stored.contract != CONTRACT_NAME && !legacy {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from {}",
+            stored.contract
+        )));
+    }
+    let from = parse_version(&stored.version)?;
+    if from > parse_version(CONTRACT_VERSION)? {
+        return Err(StdError::generic_err(format!(
+            "Cannot downgrade from {} to {}",
+            stored.version, CONTRACT_VERSION
+        )));
+    }
+
+    let mut res = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version.clone())
+        .add_attribute("to_version", CONTRACT_VERSION);
+
+    if from < vec![1, 1] {
+        // 1.0 kept the same config under both `config` and `state`.
+        let legacy = match read_legacy_config(deps.storage).may_load()? {
+            Some(legacy) => legacy,
+            None => read_state(deps.storage).load()?,
+        };
+        let config = upgrade_legacy_config(legacy);
+        config.save(deps.storage)?;
+        state(deps.storage).remove();
+        if read_phases(deps.storage).may_load()?.is_none() {
+            phases(deps.storage).save(&config.default_phases())?;
+        }
+
+        let (migrated, remaining) = migrate_legacy_whitelist(
+            deps.storage,
+            env.block.time.seconds(),
+            Some(MIGRATE_WHITELIST_BATCH),
+        )?;
+        res = res
+            .add_attribute("whitelist_migrated", migrated.to_string())
+            .add_attribute("whitelist_remaining", remaining.to_string());
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(res)
+}
+
+/// Carries a 1.0 config over, with the defaults `init` uses for the fields 1.0 did not have.
+fn upgrade_legacy_config(legacy: LegacyConfig) -> Config {
+    Config {
+        minter: legacy.minter,
+        nft_addr: legacy.nft_addr,
+        nft_base_uri: legacy.nft_base_uri,
+        nft_max_supply: legacy.nft_max_supply,
+        nft_price_amount: legacy.nft_price_amount,
+        owner: legacy.owner,
+        is_mintable: legacy.is_mintable,
+        mint_max: legacy.mint_max,
+        mint_start_time: legacy.mint_start_time,
+        nft_symbol: legacy.nft_symbol,
+        price_denom: legacy.price_denom,
+        royalty_payment_address: legacy.royalty_payment_address,
+        royalty_percentage: legacy.royalty_percentage,
+        whitelist_mint_max: legacy.whitelist_mint_max,
+        whitelist_mint_period: legacy.whitelist_mint_period,
+        whitelist_mint_price_amount: legacy.whitelist_mint_price_amount,
+        paused: legacy.paused,
+        request_expiry: DEFAULT_REQUEST_EXPIRY,
+        closed: false,
+        voucher_signer: None,
+        max_per_tx: DEFAULT_MAX_PER_TX,
+        reserved_supply: Uint128::zero(),
+    }
+}
 
-    config(&mut deps.storage).save(&config)?;
-    state(&mut deps.storage).save(&config)?;
+/// Numeric components of a dotted version, so "1.0" sorts before "1.1.0".
+fn parse_version(version: &str) -> StdResult<Vec<u64>> {
+    version
+        .split('.')
+        .map(|part| {
+            part.parse::<u64>().map_err(|_| {
+                StdError::generic_err(format!("Invalid contract version {}", version))
+            })
+        })
+        .collect()
+}
+
+/// Stores the address of the cw721 collection instantiated by `instantiate`.
+pub fn reply(
+    deps: DepsMut,
+    _env: Env,
+    msg: Reply,
+) -> StdResult<Response> {
+    if msg.id != INSTANTIATE_NFT_REPLY_ID {
+        return Err(StdError::generic_err(format!("Unknown reply id {}", msg.id)));
+    }
+    let response = msg.result.into_result().map_err(StdError::generic_err)?;
+    let nft_addr = response
+        .events
+        .iter()
+        .filter(|event| event.ty == "instantiate")
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == "_contract_address")
+        .map(|attr| attr.value.clone())
+        .ok_or_else(|| StdError::generic_err("Collection address missing from reply"))?;
 
-    set_contract_version(&mut deps.storage, "1.0")?;
+    let mut config = config(deps.storage).load()?;
+    config.nft_addr = deps.api.addr_validate(&nft_addr)?;
+    config.save(deps.storage)?;
 
-    Ok(InitResponse::default())
+    Ok(Response::new()
+        .add_attribute("action", "nft_instantiated")
+        .add_attribute("nft_addr", nft_addr))
 }
 
 pub fn execute(
-    deps: &mut Extern<DefaultApi, Storage, Querier>,
+    deps: DepsMut,
     env: Env,
+    info: MessageInfo,
     msg: ExecuteMsg,
-) -> StdResult<CosmosMsg> {
+) -> StdResult<Response> {
     match msg {
         ExecuteMsg::UpdateConfig {
             minter,
@@ -53,39 +271,100 @@ pub fn execute(
             nft_base_uri,
             nft_max_supply,
             nft_price_amount,
-            owner,
-        } => try_update_config(deps, env, minter, nft_addr, nft_base_uri, nft_max_supply, nft_price_amount, owner),
-        ExecuteMsg::Whitelist { addrs } => try_whitelist(deps, env, addrs),
-        ExecuteMsg::StartMint {} => try_start_mint(deps, env),
-        ExecuteMsg::RequestMint { addr } => try_request_mint(deps, env, addr),
+            max_per_tx,
+        } => try_update_config(
+            deps,
+            info,
+            minter,
+            nft_addr,
+            nft_base_uri,
+            nft_max_supply,
+            nft_price_amount,
+            max_per_tx,
+        ),
+        ExecuteMsg::ProposeOwner { new_owner, expiry } => {
+            try_propose_owner(deps, env, info, new_owner, expiry)
+        }
+        ExecuteMsg::AcceptOwnership {} => try_accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipTransfer {} => try_cancel_ownership_transfer(deps, info),
+        ExecuteMsg::RenounceOwnership {} => try_renounce_ownership(deps, info),
+        ExecuteMsg::Whitelist { addrs } => try_whitelist(deps, env, info, addrs),
+        ExecuteMsg::RemoveFromWhitelist { addrs } => try_remove_from_whitelist(deps, info, addrs),
+        ExecuteMsg::ClearWhitelist => try_clear_whitelist(deps, info),
+        ExecuteMsg::SetWhitelistAllocations { allocations } => {
+            try_set_whitelist_allocations(deps, env, info, allocations)
+        }
+        ExecuteMsg::MigrateWhitelist { limit } => try_migrate_whitelist(deps, env, info, limit),
+        ExecuteMsg::StartMint => try_start_mint(deps, env, info),
+        ExecuteMsg::UpdatePhases { phases } => try_update_phases(deps, env, info, phases),
+        ExecuteMsg::RequestMint {
+            addr,
+            proof,
+            quantity,
+        } => {
+            let buyer = info.sender.clone();
+            let funds = Balance::from(info.funds.clone());
+            try_request_mint(deps, env, buyer, funds, addr, proof, quantity)
+        }
         ExecuteMsg::Mint {
             extension,
             token_id,
             token_uri,
-        } => try_mint(deps, env, extension, token_id, token_uri),
-        ExecuteMsg::Pause {} => try_pause(deps, env),
-        ExecuteMsg::Unpause {} => try_unpause(deps, env),
-        ExecuteMsg::WithdrawFund {} => try_withdraw_fund(deps, env),
+            proof,
+            quantity,
+        } => {
+            let buyer = info.sender.clone();
+            let funds = Balance::from(info.funds.clone());
+            try_mint(deps, env, buyer, funds, extension, token_id, token_uri, proof, quantity)
+        }
+        ExecuteMsg::Receive(msg) => try_receive(deps, env, info, msg),
+        ExecuteMsg::MintWithVoucher {
+            voucher,
+            signature,
+            extension,
+            token_id,
+            token_uri,
+        } => try_mint_with_voucher(
+            deps, env, info, voucher, signature, extension, token_id, token_uri,
+        ),
+        ExecuteMsg::ClaimAuctionRebate { phase_id } => {
+            try_claim_auction_rebate(deps, env, info, phase_id)
+        }
+        ExecuteMsg::UpdateVoucherSigner { signer } => try_update_voucher_signer(deps, info, signer),
+        ExecuteMsg::UpdateMerkleRoot { phase_id, root } => {
+            try_update_merkle_root(deps, info, phase_id, root)
+        }
+        ExecuteMsg::FulfillRequests { tokens } => try_fulfill_requests(deps, info, tokens),
+        ExecuteMsg::CancelRequest { index } => try_cancel_request(deps, env, info, index),
+        ExecuteMsg::Pause => try_pause(deps, info),
+        ExecuteMsg::CloseMint => try_close_mint(deps, info),
+        ExecuteMsg::Airdrop { recipients } => try_airdrop(deps, info, recipients),
+        ExecuteMsg::Unpause => try_unpause(deps, info),
+        ExecuteMsg::WithdrawFund => try_withdraw_fund(deps, env, info),
+        ExecuteMsg::UpdatePayees { payees } => try_update_payees(deps, info, payees),
+        ExecuteMsg::UpdatePlatformFee { fee } => try_update_platform_fee(deps, info, fee),
+        ExecuteMsg::UpdatePlatformAdmin { admin } => try_update_platform_admin(deps, info, admin),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn try_update_config(
-    deps: &mut Extern<DefaultApi, Storage, Querier>,
-    env: Env,
+    deps: DepsMut,
+    info: MessageInfo,
     minter: Option<String>,
     nft_addr: Option<Addr>,
     nft_base_uri: Option<String>,
     nft_max_supply: Option<Uint128>,
     nft_price_amount: Option<Uint128>,
-    owner: Option<String>,
-) -> StdResult<CosmosMsg> {
-    let mut config = config(&mut deps.storage).load()?;
-    if config.owner != deps.api.canonical_address(&env.message.sender)? {
-        return Err(StdError::unauthorized());
+    max_per_tx: Option<u32>,
+) -> StdResult<Response> {
+    let mut config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
     }
 
     if let Some(new_minter) = minter {
-        config.minter = deps.api.addr_validate(&new_minter)?;
+        config.minter = deps.api.addr_validate(&new_minter)?.to_string();
     }
 
     if let Some(new_nft_addr) = nft_addr {
@@ -97,6 +376,13 @@ fn try_update_config(
     }
 
     if let Some(new_nft_max_supply) = nft_max_supply {
+        let committed = committed_supply(deps.storage, &config)?;
+        if new_nft_max_supply < committed {
+            return Err(StdError::generic_err(format!(
+                "Max supply cannot be lower than the {} tokens already minted or reserved",
+                committed
+            )));
+        }
         config.nft_max_supply = new_nft_max_supply;
     }
 
@@ -104,173 +390,1790 @@ fn try_update_config(
         config.nft_price_amount = new_nft_price_amount;
     }
 
-    if let Some(new_owner) = owner {
-        config.owner = deps.api.addr_validate(&new_owner)?;
+    if let Some(new_max_per_tx) = max_per_tx {
+        if new_max_per_tx == 0 {
+            return Err(StdError::generic_err("max_per_tx must be at least 1"));
+        }
+        config.max_per_tx = new_max_per_tx;
     }
 
-    config(&mut deps.storage).save(&config)?;
+    config.save(deps.storage)?;
 
-    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address,
-        funds: vec![],
-        msg: to_binary(&ExecuteMsg::UpdateConfig {
-            minter,
-            nft_addr,
-            nft_base_uri,
-            nft_max_supply,
-            nft_price_amount,
-            owner,
-        })?,
-    }))
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+fn try_propose_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_owner: String,
+    expiry: Option<u64>,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+    if let Some(expiry) = expiry {
+        if expiry <= env.block.time.seconds() {
+            return Err(StdError::generic_err("Ownership proposal expiry must be in the future"));
+        }
+    }
+
+    let proposal = PendingOwner {
+        new_owner: deps.api.addr_validate(&new_owner)?,
+        expiry,
+    };
+    pending_owner(deps.storage).save(&proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_owner")
+        .add_attribute("new_owner", proposal.new_owner))
+}
+
+fn try_accept_ownership(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    let proposal = read_pending_owner(deps.storage)
+        .may_load()?
+        .ok_or_else(|| StdError::generic_err("No ownership transfer is pending."))?;
+    if info.sender != proposal.new_owner {
+        return Err(unauthorized());
+    }
+    if proposal.expiry.is_some_and(|expiry| env.block.time.seconds() >= expiry) {
+        return Err(StdError::generic_err("Ownership proposal has expired."));
+    }
+
+    let mut config = config(deps.storage).load()?;
+    config.owner = proposal.new_owner.to_string();
+    config.save(deps.storage)?;
+    pending_owner(deps.storage).remove();
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", config.owner))
+}
+
+fn try_cancel_ownership_transfer(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+    if read_pending_owner(deps.storage).may_load()?.is_none() {
+        return Err(StdError::generic_err("No ownership transfer is pending."));
+    }
+
+    pending_owner(deps.storage).remove();
+
+    Ok(Response::new().add_attribute("action", "cancel_ownership_transfer"))
+}
+
+/// Leaves the contract without an owner. An empty owner never matches a sender, so every
+/// owner-only message is rejected from then on.
+fn try_renounce_ownership(deps: DepsMut, info: MessageInfo) -> StdResult<Response> {
+    let mut config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+    // Without an owner the payees can no longer change, so they have to be set first.
+    if read_payees(deps.storage).may_load()?.is_none() {
+        return Err(StdError::generic_err(
+            "Payees must be set before ownership can be renounced.",
+        ));
+    }
+
+    config.owner = String::new();
+    config.save(deps.storage)?;
+    pending_owner(deps.storage).remove();
+
+    Ok(Response::new().add_attribute("action", "renounce_ownership"))
 }
 
 fn try_whitelist(
-    deps: &mut Extern<DefaultApi, Storage, Querier>,
+    deps: DepsMut,
     env: Env,
+    info: MessageInfo,
     addrs: Vec<Addr>,
-) -> StdResult<CosmosMsg> {
-    let mut config = config(&mut deps.storage).load()?;
-    if config.owner != deps.api.canonical_address(&env.message.sender)? {
-        return Err(StdError::unauthorized());
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
     }
 
-    let mut whitelist = whitelist(&mut deps.storage);
+    let mut added = 0u64;
     for addr in addrs {
-        whitelist.whitelist(addr)?;
+        if add_to_whitelist(deps.storage, &addr, env.block.time.seconds())? {
+            added += 1;
+        }
     }
 
-    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address,
-        funds: vec![],
-        msg: to_binary(&ExecuteMsg::Whitelist { addrs })?,
-    }))
+    Ok(Response::new()
+        .add_attribute("action", "whitelist")
+        .add_attribute("count", added.to_string()))
 }
 
-fn try_start_mint(deps: &mut Extern<DefaultApi, Storage, Querier>, env: Env) -> StdResult<CosmosMsg> {
-    let mut config = config(&mut deps.storage).load()?;
-    if config.owner != deps.api.canonical_address(&env.message.sender)? {
-        return Err(StdError::unauthorized());
+fn try_remove_from_whitelist(
+    deps: DepsMut,
+    info: MessageInfo,
+    addrs: Vec<Addr>,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+
+    let generation = read_whitelist_generation(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let mut legacy = whitelist(deps.storage).may_load()?;
+    let mut removed = 0u64;
+    let mut removed_entries = 0u64;
+    for addr in addrs {
+        let mut found = false;
+        if load_whitelist_entry(deps.storage, &addr)?.is_some() {
+            whitelist_entries(deps.storage, generation).remove(addr.as_bytes());
+            removed_entries += 1;
+            found = true;
+        }
+        // Addresses not migrated yet are only in the legacy whitelist.
+        if let Some(legacy) = legacy.as_mut() {
+            let before = legacy.addresses.len();
+            legacy.addresses.retain(|legacy_addr| *legacy_addr != addr);
+            found |= legacy.addresses.len() != before;
+        }
+        if found {
+            removed += 1;
+        }
     }
+    match legacy {
+        Some(legacy) if legacy.addresses.is_empty() => whitelist(deps.storage).remove(),
+        Some(legacy) => whitelist(deps.storage).save(&legacy)?,
+        None => {}
+    }
+    let size = read_whitelist_size(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    whitelist_size(deps.storage).save(&(size - removed_entries))?;
 
-    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address,
-        funds: vec![],
-        msg: to_binary(&ExecuteMsg::StartMint {})?,
-    }))
+    Ok(Response::new()
+        .add_attribute("action", "remove_from_whitelist")
+        .add_attribute("count", removed.to_string()))
+}
+
+fn try_clear_whitelist(deps: DepsMut, info: MessageInfo) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+
+    let generation = read_whitelist_generation(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    whitelist_generation(deps.storage).save(&(generation + 1))?;
+    whitelist_size(deps.storage).save(&0)?;
+    whitelist(deps.storage).remove();
+
+    Ok(Response::new().add_attribute("action", "clear_whitelist"))
+}
+
+fn try_set_whitelist_allocations(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    allocations: Vec<WhitelistAllocation>,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+
+    let count = allocations.len();
+    for allocation in allocations {
+        let addr = deps.api.addr_validate(&allocation.addr)?;
+        let added_at = load_whitelist_entry(deps.storage, &addr)?
+            .map_or(env.block.time.seconds(), |entry| entry.added_at);
+        save_whitelist_entry(
+            deps.storage,
+            &addr,
+            &WhitelistEntry {
+                added_at,
+                mint_allocation: Some(allocation.mint_allocation),
+                price: allocation.price,
+            },
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_whitelist_allocations")
+        .add_attribute("count", count.to_string()))
+}
+
+fn try_migrate_whitelist(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+
+    let (migrated, remaining) =
+        migrate_legacy_whitelist(deps.storage, env.block.time.seconds(), limit.map(|limit| limit as usize))?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate_whitelist")
+        .add_attribute("migrated", migrated.to_string())
+        .add_attribute("remaining", remaining.to_string()))
+}
+
+fn load_whitelist_entry(storage: &dyn Storage, addr: &Addr) -> StdResult<Option<WhitelistEntry>> {
+    let generation = read_whitelist_generation(storage).may_load()?.unwrap_or_default();
+    read_whitelist_entries(storage, generation).may_load(addr.as_bytes())
+}
+
+/// Like `load_whitelist_entry`, but also finds addresses still waiting in the legacy whitelist
+/// while `MigrateWhitelist` works through it.
+fn find_whitelist_entry(storage: &dyn Storage, addr: &Addr) -> StdResult<Option<WhitelistEntry>> {
+    if let Some(entry) = load_whitelist_entry(storage, addr)? {
+        return Ok(Some(entry));
+    }
+    let legacy = read_whitelist(storage).may_load()?;
+    if legacy.is_some_and(|legacy| legacy.addresses.contains(addr)) {
+        return Ok(Some(WhitelistEntry {
+            added_at: 0,
+            mint_allocation: None,
+            price: None,
+        }));
+    }
+    Ok(None)
+}
+
+fn save_whitelist_entry(storage: &mut dyn Storage, addr: &Addr, entry: &WhitelistEntry) -> StdResult<()> {
+    if load_whitelist_entry(storage, addr)?.is_none() {
+        let size = read_whitelist_size(storage).may_load()?.unwrap_or_default();
+        whitelist_size(storage).save(&(size + 1))?;
+    }
+    let generation = read_whitelist_generation(storage).may_load()?.unwrap_or_default();
+    whitelist_entries(storage, generation).save(addr.as_bytes(), entry)
+}
+
+/// Adds `addr` to the whitelist, returning `false` if it was already on it.
+fn add_to_whitelist(storage: &mut dyn Storage, addr: &Addr, time: u64) -> StdResult<bool> {
+    if load_whitelist_entry(storage, addr)?.is_some() {
+        return Ok(false);
+    }
+
+    let entry = WhitelistEntry {
+        added_at: time,
+        mint_allocation: None,
+        price: None,
+    };
+    save_whitelist_entry(storage, addr, &entry)?;
+    Ok(true)
+}
+
+/// Moves up to `limit` addresses from the legacy `Vec` whitelist into `whitelist_entries`,
+/// dropping the legacy item once it is empty. Returns how many moved and how many remain.
+fn migrate_legacy_whitelist(
+    storage: &mut dyn Storage,
+    time: u64,
+    limit: Option<usize>,
+) -> StdResult<(usize, usize)> {
+    let mut legacy = match whitelist(storage).may_load()? {
+        Some(legacy) => legacy,
+        None => return Ok((0, 0)),
+    };
+
+    let take = limit.unwrap_or(legacy.addresses.len()).min(legacy.addresses.len());
+    let batch = legacy.addresses.split_off(legacy.addresses.len() - take);
+    for addr in batch.iter() {
+        add_to_whitelist(storage, addr, time)?;
+    }
+
+    if legacy.addresses.is_empty() {
+        whitelist(storage).remove();
+    } else {
+        whitelist(storage).save(&legacy)?;
+    }
+
+    Ok((batch.len(), legacy.addresses.len()))
+}
+
+fn try_start_mint(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    let mut config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+    if config.closed {
+        return Err(StdError::generic_err("Minting is closed."));
+    }
+
+    // Bring a future first phase forward; a schedule that is already running is left alone.
+    let mut schedule = read_phases(deps.storage).load()?;
+    if let Some(first) = schedule.first_mut() {
+        if first.start_time > env.block.time.seconds() {
+            first.start_time = env.block.time.seconds();
+            validate_phases(&schedule)?;
+            phases(deps.storage).save(&schedule)?;
+        }
+    }
+
+    config.is_mintable = true;
+    config.save(deps.storage)?;
+
+    Ok(Response::new().add_attribute("action", "start_mint"))
+}
+
+fn try_update_phases(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_phases: Vec<MintPhaseConfig>,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+
+    // Phases that have already started are locked; only future ones may change.
+    let current = read_phases(deps.storage).load()?;
+    for (id, phase) in current.iter().enumerate() {
+        if phase.start_time <= env.block.time.seconds() && new_phases.get(id) != Some(phase) {
+            return Err(StdError::generic_err(format!(
+                "Phase {} has already started and cannot be changed",
+                id
+            )));
+        }
+    }
+    for (id, phase) in new_phases.iter().enumerate() {
+        if phase.start_time <= env.block.time.seconds() && current.get(id) != Some(phase) {
+            return Err(StdError::generic_err(format!(
+                "Phase {} must start in the future",
+                phase.name
+            )));
+        }
+    }
+    validate_phases(&new_phases)?;
+    if let Some(fee) = read_platform_fee(deps.storage).may_load()? {
+        check_flat_fees(&fee, &new_phases)?;
+    }
+
+    phases(deps.storage).save(&new_phases)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_phases")
+        .add_attribute("phases", new_phases.len().to_string()))
+}
+
+fn try_update_merkle_root(
+    deps: DepsMut,
+    info: MessageInfo,
+    phase_id: u32,
+    root: String,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+
+    merkle::decode_hash(&root)?;
+    let mut schedule = read_phases(deps.storage).load()?;
+    match schedule.get_mut(phase_id as usize) {
+        Some(MintPhaseConfig {
+            allowlist: Some(Allowlist::Merkle { root: current }),
+            ..
+        }) => *current = root.clone(),
+        Some(_) => {
+            return Err(StdError::generic_err(format!(
+                "Phase {} does not use a merkle allowlist",
+                phase_id
+            )))
+        }
+        None => return Err(StdError::generic_err(format!("Unknown phase {}", phase_id))),
+    }
+    phases(deps.storage).save(&schedule)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_merkle_root")
+        .add_attribute("phase_id", phase_id.to_string())
+        .add_attribute("root", root))
+}
+
+fn try_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> StdResult<Response> {
+    if !info.funds.is_empty() {
+        return Err(StdError::generic_err("Native funds cannot be sent with a cw20 payment"));
+    }
+
+    // The cw20 contract calls us, on behalf of the holder who sent the tokens.
+    let buyer = deps.api.addr_validate(&wrapper.sender)?;
+    let token = info.sender.clone();
+    if read_cw20_escrow(deps.storage).may_load(token.as_bytes())?.is_none() {
+        cw20_escrow(deps.storage).save(token.as_bytes(), &Uint128::zero())?;
+    }
+    let funds = Balance::Cw20(Cw20CoinVerified {
+        address: token,
+        amount: wrapper.amount,
+    });
+
+    match from_json(&wrapper.msg)? {
+        ReceiveMsg::RequestMint {
+            addr,
+            proof,
+            quantity,
+        } => try_request_mint(deps, env, buyer, funds, addr, proof, quantity),
+        ReceiveMsg::Mint {
+            extension,
+            token_id,
+            token_uri,
+            proof,
+            quantity,
+        } => try_mint(deps, env, buyer, funds, extension, token_id, token_uri, proof, quantity),
+    }
 }
 
 fn try_request_mint(
-    deps: &mut Extern<DefaultApi, Storage, Querier>,
+    mut deps: DepsMut,
     env: Env,
+    requester: Addr,
+    funds: Balance,
     addr: Addr,
-) -> StdResult<CosmosMsg> {
-    let config = config(&mut deps.storage).load()?;
-    if !config.is_mintable {
-        return Err(StdError::generic_err("Minting is not allowed at the moment."));
+    proof: Option<MerkleProof>,
+    quantity: Option<u32>,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    let quantity = check_quantity(&config, quantity)?;
+    let (phase_id, paid, surplus) =
+        authorize_mint(deps.branch(), &env, &config, &requester, &addr, proof.as_ref(), quantity, &funds)?;
+    let schedule = read_phases(deps.storage).load()?;
+    if let PriceMode::DutchAuction(auction) = &schedule[phase_id as usize].price_mode {
+        if auction.rebate {
+            return Err(StdError::generic_err(
+                "Token requests are not available in rebate auctions",
+            ));
+        }
     }
+    hold_escrow(deps.storage, &paid)?;
+
+    // Each request carries its share of the payment so it can be refunded on its own. Curve
+    // prices do not split evenly, so the first request also takes the remainder.
+    let share = paid.amount() / Uint128::from(quantity);
+    let remainder = paid.amount() - share * Uint128::from(quantity);
 
-    let whitelist = whitelist(&mut deps.storage);
-    if !whitelist.is_whitelisted(&addr)? {
-        return Err(StdError::generic_err("Address is not whitelisted for minting."));
+    let first = read_token_requests_count(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    for n in 0..quantity as u64 {
+        let request = TokenRequest {
+            requester: requester.clone(),
+            recipient: addr.clone(),
+            paid: paid.with_amount(if n == 0 { share + remainder } else { share }),
+            phase_id,
+            requested_at: env.block.time.seconds(),
+            expires_at: env.block.time.seconds() + config.request_expiry,
+            status: TokenRequestStatus::Pending,
+        };
+        request.save(deps.storage, first + n)?;
     }
+    token_requests_count(deps.storage).save(&(first + quantity as u64))?;
+    let pending = read_pending_token_requests(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    pending_token_requests(deps.storage).save(&(pending + quantity as u64))?;
 
-    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address,
-        funds: vec![],
-        msg: to_binary(&ExecuteMsg::RequestMint { addr })?,
-    }))
+    let res = Response::new()
+        .add_attribute("action", "request_mint")
+        .add_attribute("index", first.to_string())
+        .add_attribute("quantity", quantity.to_string())
+        .add_attribute("recipient", addr)
+        .add_attribute("paid", paid.to_string());
+    refund_surplus(res, &requester, surplus)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn try_mint(
-    deps: &mut Extern<DefaultApi, Storage, Querier>,
+    mut deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    funds: Balance,
+    extension: Option<Metadata>,
+    token_id: String,
+    token_uri: Option<String>,
+    proof: Option<MerkleProof>,
+    quantity: Option<u32>,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    let quantity = check_quantity(&config, quantity)?;
+    let token_ids: Vec<String> = if quantity == 1 {
+        vec![token_id]
+    } else {
+        (1..=quantity).map(|n| format!("{}-{}", token_id, n)).collect()
+    };
+    for token_id in token_ids.iter() {
+        check_token_id(token_id)?;
+    }
+    let (phase_id, paid, surplus) =
+        authorize_mint(deps.branch(), &env, &config, &sender, &sender, proof.as_ref(), quantity, &funds)?;
+    // Rebate auctions charge the platform fee once the clearing price is known.
+    let fee = if record_auction_bid(deps.branch(), &env, &sender, phase_id, &paid, quantity)? {
+        None
+    } else {
+        charge_platform_fee(deps.storage, &paid, quantity)?
+    };
+
+    let current_supply = read_supply(deps.storage).may_load()?.unwrap_or_default();
+    supply(deps.storage).save(&(current_supply + Uint128::from(quantity)))?;
+
+    let mut messages = Vec::with_capacity(token_ids.len());
+    for token_id in token_ids.iter() {
+        let extension = token_extension(deps.branch(), &config, token_id, extension.clone(), false)?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.nft_addr.to_string(),
+            funds: vec![],
+            msg: to_json_binary(&Cw721ExecuteMsg::Mint {
+                token_id: token_id.clone(),
+                owner: sender.to_string(),
+                token_uri: token_uri.clone(),
+                extension: Some(extension),
+            })?,
+        }));
+    }
+
+    let res = Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "mint")
+        .add_attribute("token_id", token_ids.join(","));
+    let res = pay_platform_fees(deps.storage, res, fee.into_iter().collect())?;
+    refund_surplus(res, &sender, surplus)
+}
+
+fn try_airdrop(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    recipients: Vec<(String, u32)>,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+
+    let total = recipients
+        .iter()
+        .fold(Uint128::zero(), |total, (_, amount)| total + Uint128::from(*amount));
+    let remaining = airdrop_remaining(deps.storage, &config)?;
+    if total > remaining {
+        return Err(StdError::generic_err(format!(
+            "Airdrop of {} tokens exceeds the {} left in the reserved supply",
+            total, remaining
+        )));
+    }
+
+    let mut next = read_airdropped(deps.storage).may_load()?.unwrap_or_default();
+    let mut messages = vec![];
+    for (recipient, amount) in recipients {
+        let recipient = deps.api.addr_validate(&recipient)?;
+        for _ in 0..amount {
+            next += Uint128::new(1);
+            let token_id = format!("{}{}", RESERVED_TOKEN_PREFIX, next);
+            let extension = token_extension(deps.branch(), &config, &token_id, None, true)?;
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: config.nft_addr.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&Cw721ExecuteMsg::Mint {
+                    token_id,
+                    owner: recipient.to_string(),
+                    token_uri: None,
+                    extension: Some(extension),
+                })?,
+            }));
+        }
+    }
+    airdropped(deps.storage).save(&next)?;
+    let current_supply = read_supply(deps.storage).may_load()?.unwrap_or_default();
+    supply(deps.storage).save(&(current_supply + total))?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "airdrop")
+        .add_attribute("quantity", total.to_string()))
+}
+
+/// Records a purchase in a rebate auction, holding the whole payment until the buyer claims
+/// their rebate. Returns whether the phase is a rebate auction.
+fn record_auction_bid(
+    deps: DepsMut,
+    env: &Env,
+    buyer: &Addr,
+    phase_id: u32,
+    paid: &Payment,
+    quantity: u32,
+) -> StdResult<bool> {
+    let schedule = read_phases(deps.storage).load()?;
+    let phase = &schedule[phase_id as usize];
+    let auction = match &phase.price_mode {
+        PriceMode::DutchAuction(auction) if auction.rebate => auction,
+        _ => return Ok(false),
+    };
+
+    let floor = phase.prices[0].amount;
+    let price = auction.price_at(floor, phase.start_time, env.block.time.seconds());
+    let mut bid = read_auction_bids(deps.storage, phase_id)
+        .may_load(buyer.as_bytes())?
+        .unwrap_or_default();
+    bid.quantity += quantity;
+    bid.paid += paid.amount();
+    auction_bids(deps.storage, phase_id).save(buyer.as_bytes(), &bid)?;
+    auction_clearing_price(deps.storage).save(&phase_id.to_be_bytes(), &price)?;
+
+    hold_escrow(deps.storage, paid)?;
+    Ok(true)
+}
+
+fn try_claim_auction_rebate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    phase_id: u32,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    let schedule = read_phases(deps.storage).load()?;
+    let phase = schedule
+        .get(phase_id as usize)
+        .ok_or_else(|| StdError::generic_err(format!("Unknown phase {}", phase_id)))?;
+    let sender = info.sender.clone();
+    let bid = read_auction_bids(deps.storage, phase_id)
+        .may_load(sender.as_bytes())?
+        .ok_or_else(|| StdError::generic_err("No auction rebate to claim"))?;
+
+    // The auction settles when the phase ends or runs out of tokens.
+    let sold = read_phase_supply(deps.storage)
+        .may_load(&phase_id.to_be_bytes())?
+        .unwrap_or_default();
+    let settled = phase.end_time.is_some_and(|end| env.block.time.seconds() >= end)
+        || phase.supply_max.is_some_and(|supply_max| sold >= supply_max)
+        || matches!(
+            current_phase(deps.storage, &config, &schedule, env.block.time.seconds())?,
+            MintPhase::SoldOut | MintPhase::Closed
+        );
+    if !settled {
+        return Err(StdError::generic_err("The auction has not settled yet."));
+    }
+
+    // The buyer pays the clearing price, and the platform fee is charged on that.
+    let floor = &phase.prices[0];
+    let clearing_price = read_auction_clearing_price(deps.storage)
+        .load(&phase_id.to_be_bytes())?;
+    let paid = Payment::Native(Coin {
+        denom: floor.denom.clone(),
+        amount: bid.paid,
+    });
+    let cost = paid.with_amount(clearing_price * Uint128::from(bid.quantity));
+    let rebate = paid.with_amount(bid.paid.checked_sub(cost.amount())?);
+    auction_bids(deps.storage, phase_id).remove(sender.as_bytes());
+    release_escrow(deps.storage, &paid)?;
+    let fee = charge_platform_fee(deps.storage, &cost, bid.quantity)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "claim_auction_rebate")
+        .add_attribute("rebate", rebate.to_string());
+    if !rebate.amount().is_zero() {
+        res = res.add_messages(payment_msgs(&sender, vec![rebate])?);
+    }
+    pay_platform_fees(deps.storage, res, fee.into_iter().collect())
+}
+
+/// Total native price of `quantity` tokens in `phase` at `time`, for each accepted denom.
+fn batch_prices(
+    storage: &dyn Storage,
+    phase: &MintPhaseConfig,
+    time: u64,
+    quantity: u32,
+) -> StdResult<Vec<Coin>> {
+    let quantity_u128 = Uint128::from(quantity);
+    phase
+        .prices
+        .iter()
+        .map(|price| {
+            let amount = match &phase.price_mode {
+                PriceMode::Fixed => price.amount * quantity_u128,
+                PriceMode::DutchAuction(auction) => {
+                    auction.price_at(price.amount, phase.start_time, time) * quantity_u128
+                }
+                PriceMode::BondingCurve(curve) => {
+                    // Every token in the batch moves the curve on by one.
+                    let sold = u64::try_from(sold_supply(storage)?.u128())
+                        .map_err(|_| StdError::generic_err("Supply out of range"))?;
+                    let mut total = Uint128::zero();
+                    for n in 0..u64::from(quantity) {
+                        total = total.checked_add(curve.price_at(price.amount, sold + n)?)?;
+                    }
+                    total
+                }
+            };
+            Ok(Coin {
+                denom: price.denom.clone(),
+                amount,
+            })
+        })
+        .collect()
+}
+
+/// Fills in the royalty fields of a token's metadata from the collection default. Royalties the
+/// metadata sets itself are checked and recorded for `RoyaltyInfo`, but only the minter and owner
+/// (`allow_royalties`) may set them; buyers always get the collection default.
+fn token_extension(
+    deps: DepsMut,
+    config: &Config,
+    token_id: &str,
+    extension: Option<Metadata>,
+    allow_royalties: bool,
+) -> StdResult<Metadata> {
+    let mut extension = extension.unwrap_or_default();
+    if extension.royalty_payment_address.is_some() || extension.royalty_percentage.is_some() {
+        if !allow_royalties {
+            return Err(StdError::generic_err(
+                "Only the minter can set royalties for a token",
+            ));
+        }
+        let royalty = Royalty {
+            payment_address: match &extension.royalty_payment_address {
+                Some(addr) => deps.api.addr_validate(addr)?.to_string(),
+                None => config.royalty_payment_address.clone(),
+            },
+            percentage: extension
+                .royalty_percentage
+                .unwrap_or(config.royalty_percentage),
+        };
+        check_royalty_percentage(royalty.percentage)?;
+        token_royalties(deps.storage).save(token_id.as_bytes(), &royalty)?;
+    }
+    extension
+        .royalty_payment_address
+        .get_or_insert_with(|| config.royalty_payment_address.clone());
+    extension
+        .royalty_percentage
+        .get_or_insert(config.royalty_percentage);
+    Ok(extension)
+}
+
+/// Keeps token ids in the range `Airdrop` mints from free for it.
+fn check_token_id(token_id: &str) -> StdResult<()> {
+    if token_id.starts_with(RESERVED_TOKEN_PREFIX) {
+        return Err(StdError::generic_err(format!(
+            "Token id {} is reserved for airdrops",
+            token_id
+        )));
+    }
+    Ok(())
+}
+
+fn check_royalty_percentage(percentage: u32) -> StdResult<()> {
+    if percentage > 100 {
+        return Err(StdError::generic_err(format!(
+            "Royalty percentage {} exceeds 100",
+            percentage
+        )));
+    }
+    Ok(())
+}
+
+/// Defaults `quantity` to one token and checks it against `max_per_tx`.
+fn check_quantity(config: &Config, quantity: Option<u32>) -> StdResult<u32> {
+    let quantity = quantity.unwrap_or(1);
+    if quantity == 0 {
+        return Err(StdError::generic_err("Quantity must be at least 1"));
+    }
+    if quantity > config.max_per_tx {
+        return Err(StdError::generic_err(format!(
+            "Quantity {} exceeds the limit of {} tokens per transaction",
+            quantity, config.max_per_tx
+        )));
+    }
+    Ok(quantity)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn try_mint_with_voucher(
+    mut deps: DepsMut,
     env: Env,
+    info: MessageInfo,
+    voucher: MintVoucher,
+    signature: Binary,
     extension: Option<Metadata>,
     token_id: String,
     token_uri: Option<String>,
-) -> StdResult<CosmosMsg> {
-    let mut config = config(&mut deps.storage).load()?;
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if config.paused {
+        return Err(StdError::generic_err("Minting is paused."));
+    }
+    if config.closed {
+        return Err(StdError::generic_err("Minting is closed."));
+    }
     if !config.is_mintable {
-        return Err(StdError::generic_err("Minting is not allowed at the moment."));
+        return Err(StdError::generic_err("Minting has not started yet."));
     }
+    check_token_id(&token_id)?;
 
-    // Perform minting logic here...
+    let signer = config
+        .voucher_signer
+        .as_ref()
+        .ok_or_else(|| StdError::generic_err("Voucher minting is not enabled."))?;
+    let digest = voucher_digest(&env.contract.address, &voucher)?;
+    let valid = match signer.algorithm {
+        SignatureAlgorithm::Secp256k1 => deps
+            .api
+            .secp256k1_verify(&digest, &signature, &signer.pubkey)
+            .map_err(|err| StdError::generic_err(err.to_string()))?,
+        SignatureAlgorithm::Ed25519 => deps
+            .api
+            .ed25519_verify(&digest, &signature, &signer.pubkey)
+            .map_err(|err| StdError::generic_err(err.to_string()))?,
+    };
+    if !valid {
+        return Err(StdError::generic_err("Invalid voucher signature."));
+    }
+    if env.block.time.seconds() > voucher.expiry {
+        return Err(StdError::generic_err("Voucher has expired."));
+    }
 
-    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address,
-        funds: vec![],
-        msg: to_binary(&ExecuteMsg::Mint {
-            extension,
-            token_id,
-            token_uri,
-        })?,
-    }))
+    let nonce_key = voucher.nonce.to_be_bytes();
+    let used = read_voucher_nonces(deps.storage)
+        .may_load(&nonce_key)?
+        .unwrap_or_default();
+    if used >= voucher.max_quantity {
+        return Err(StdError::generic_err(format!(
+            "Voucher {} has already been used for {} tokens",
+            voucher.nonce, used
+        )));
+    }
+
+    assert_supply_available(deps.storage, &config, Uint128::new(1))?;
+    let surplus =
+        check_payment(&info.funds, &voucher.price.denom, voucher.price.amount)?;
+
+    let sender = info.sender.clone();
+    let recipient = deps.api.addr_validate(&voucher.recipient)?;
+    voucher_nonces(deps.storage).save(&nonce_key, &(used + 1))?;
+    let fee = charge_platform_fee(deps.storage, &Payment::Native(voucher.price.clone()), 1)?;
+    let current_supply = read_supply(deps.storage).may_load()?.unwrap_or_default();
+    supply(deps.storage).save(&(current_supply + Uint128::new(1)))?;
+    let extension = token_extension(deps.branch(), &config, &token_id, extension, false)?;
+
+    let res = Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.nft_addr.to_string(),
+            funds: vec![],
+            msg: to_json_binary(&Cw721ExecuteMsg::Mint {
+                token_id: token_id.clone(),
+                owner: recipient.to_string(),
+                token_uri,
+                extension: Some(extension),
+            })?,
+        }))
+        .add_attribute("action", "mint_with_voucher")
+        .add_attribute("nonce", voucher.nonce.to_string())
+        .add_attribute("token_id", token_id);
+    let res = pay_platform_fees(deps.storage, res, fee.into_iter().collect())?;
+    let surplus = surplus.into_iter().map(Payment::Native).collect();
+    refund_surplus(res, &sender, surplus)
+}
+
+/// Message signed by the voucher signer: sha256 of the contract address followed by the JSON
+/// encoded voucher, so a voucher cannot be replayed against another minter.
+pub fn voucher_digest(contract: &Addr, voucher: &MintVoucher) -> StdResult<Vec<u8>> {
+    let mut hasher = Sha256::new();
+    hasher.update(contract.as_bytes());
+    hasher.update(to_json_vec(voucher)?);
+    Ok(hasher.finalize().to_vec())
+}
+
+fn try_update_voucher_signer(
+    deps: DepsMut,
+    info: MessageInfo,
+    signer: Option<VoucherSigner>,
+) -> StdResult<Response> {
+    let mut config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+
+    config.voucher_signer = signer;
+    config.save(deps.storage)?;
+
+    Ok(Response::new().add_attribute("action", "update_voucher_signer"))
+}
+
+fn try_fulfill_requests(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    tokens: Vec<FulfillToken>,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.minter {
+        return Err(unauthorized());
+    }
+    for token in tokens.iter() {
+        check_token_id(&token.token_id)?;
+    }
+
+    let count = read_token_requests_count(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let mut head = read_token_requests_head(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+
+    let fulfilled = tokens.len();
+    let mut messages = vec![];
+    let mut fees: Vec<Payment> = vec![];
+    for token in tokens {
+        // Skip anything that left the pending state since it was queued.
+        let (index, mut request) = loop {
+            if head >= count {
+                return Err(StdError::generic_err(format!(
+                    "Only {} pending token requests to fulfill",
+                    messages.len()
+                )));
+            }
+            let request = TokenRequest::load(deps.storage, head)?;
+            head += 1;
+            if request.status == TokenRequestStatus::Pending {
+                break (head - 1, request);
+            }
+        };
+
+        let extension =
+            token_extension(deps.branch(), &config, &token.token_id, token.extension, true)?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.nft_addr.to_string(),
+            funds: vec![],
+            msg: to_json_binary(&Cw721ExecuteMsg::Mint {
+                token_id: token.token_id.clone(),
+                owner: request.recipient.to_string(),
+                token_uri: token.token_uri,
+                extension: Some(extension),
+            })?,
+        }));
+
+        request.status = TokenRequestStatus::Fulfilled {
+            token_id: token.token_id,
+        };
+        request.save(deps.storage, index)?;
+        release_escrow(deps.storage, &request.paid)?;
+        if let Some(fee) = charge_platform_fee(deps.storage, &request.paid, 1)? {
+            match fees.iter_mut().find(|total| total.key() == fee.key()) {
+                Some(total) => *total = total.with_amount(total.amount() + fee.amount()),
+                None => fees.push(fee),
+            }
+        }
+    }
+
+    token_requests_head(deps.storage).save(&head)?;
+    let pending = read_pending_token_requests(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    pending_token_requests(deps.storage).save(&(pending - fulfilled as u64))?;
+    let current_supply = read_supply(deps.storage).may_load()?.unwrap_or_default();
+    supply(deps.storage).save(&(current_supply + Uint128::from(fulfilled as u128)))?;
+
+    let res = Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "fulfill_requests")
+        .add_attribute("fulfilled", fulfilled.to_string());
+    pay_platform_fees(deps.storage, res, fees)
+}
+
+fn try_cancel_request(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    index: Uint128,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    let index = u64::try_from(index.u128())
+        .map_err(|_| StdError::generic_err("Token request index out of range"))?;
+    let mut request = TokenRequest::load(deps.storage, index)?;
+
+    if info.sender != request.requester {
+        return Err(unauthorized());
+    }
+    if request.status != TokenRequestStatus::Pending {
+        return Err(StdError::generic_err("Token request is not pending."));
+    }
+    if !config.closed && env.block.time.seconds() < request.expires_at {
+        return Err(StdError::generic_err(format!(
+            "Token request cannot be cancelled before {}",
+            request.expires_at
+        )));
+    }
+
+    request.status = TokenRequestStatus::Refunded;
+    request.save(deps.storage, index)?;
+    release_escrow(deps.storage, &request.paid)?;
+
+    // The refunded request no longer uses up the recipient's or the phase's allocation.
+    let minted = read_mint_counts(deps.storage, request.phase_id)
+        .may_load(request.recipient.as_bytes())?
+        .unwrap_or_default();
+    mint_counts(deps.storage, request.phase_id)
+        .save(request.recipient.as_bytes(), &minted.saturating_sub(1))?;
+    let phase_key = request.phase_id.to_be_bytes();
+    let sold = read_phase_supply(deps.storage)
+        .may_load(&phase_key)?
+        .unwrap_or_default();
+    phase_supply(deps.storage).save(&phase_key, &sold.saturating_sub(Uint128::new(1)))?;
+
+    let pending = read_pending_token_requests(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    pending_token_requests(deps.storage).save(&(pending - 1))?;
+    let refunded = read_refunded_token_requests(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    refunded_token_requests(deps.storage).save(&(refunded + 1))?;
+
+    Ok(Response::new()
+        .add_messages(payment_msgs(&request.requester, vec![request.paid.clone()])?)
+        .add_attribute("action", "cancel_request")
+        .add_attribute("index", index.to_string())
+        .add_attribute("refund", request.paid.to_string()))
+}
+
+/// Checks that `recipient` may mint `quantity` tokens in the active phase, takes payment for
+/// them from `payer` and records them against the phase and address caps. Returns the phase
+/// id, the amount charged and any surplus funds to refund.
+#[allow(clippy::too_many_arguments)]
+fn authorize_mint(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    payer: &Addr,
+    recipient: &Addr,
+    proof: Option<&MerkleProof>,
+    quantity: u32,
+    funds: &Balance,
+) -> StdResult<(u32, Payment, Vec<Payment>)> {
+    if config.paused {
+        return Err(StdError::generic_err("Minting is paused."));
+    }
+
+    let schedule = read_phases(deps.storage).load()?;
+    let phase_id = match current_phase(deps.storage, config, &schedule, env.block.time.seconds())? {
+        MintPhase::Active { id } => id,
+        MintPhase::NotStarted => {
+            return Err(StdError::generic_err("Minting has not started yet."))
+        }
+        MintPhase::BetweenPhases => {
+            return Err(StdError::generic_err("No mint phase is open right now."))
+        }
+        MintPhase::SoldOut => {
+            return Err(StdError::generic_err(format!(
+                "Sold out: all {} tokens have been minted or reserved",
+                config.nft_max_supply
+            )))
+        }
+        MintPhase::Closed => return Err(StdError::generic_err("Minting is closed.")),
+    };
+    let phase = &schedule[phase_id as usize];
+
+    let (mint_max, price_override) = check_allowlist(deps.storage, phase, recipient, proof)?;
+    // Otherwise anyone could use up a wallet's allocation by requesting on its behalf.
+    if payer != recipient && (phase.allowlist.is_some() || mint_max.is_some()) {
+        return Err(StdError::generic_err(
+            "Tokens can only be requested for your own address in this phase.",
+        ));
+    }
+
+    assert_supply_available(deps.storage, config, Uint128::from(quantity))?;
+
+    let phase_key = phase_id.to_be_bytes();
+    let sold = read_phase_supply(deps.storage)
+        .may_load(&phase_key)?
+        .unwrap_or_default();
+    if let Some(supply_max) = phase.supply_max {
+        if sold + Uint128::from(quantity) > supply_max {
+            return Err(StdError::generic_err(format!(
+                "Phase {} is sold out: all {} tokens have been minted or reserved",
+                phase.name, supply_max
+            )));
+        }
+    }
+
+    let minted = read_mint_counts(deps.storage, phase_id)
+        .may_load(recipient.as_bytes())?
+        .unwrap_or_default();
+    if let Some(mint_max) = mint_max {
+        if Uint128::from(minted + quantity) > mint_max {
+            return Err(StdError::generic_err(format!(
+                "Mint limit reached: {} may mint at most {} tokens in this phase",
+                recipient, mint_max
+            )));
+        }
+    }
+
+    let (paid, surplus) = match funds {
+        Balance::Native(coins) => {
+            // Use the first option whose denom is attached, so anything else is a surplus.
+            let prices = batch_prices(deps.storage, phase, env.block.time.seconds(), quantity)?;
+            let (index, option) = prices
+                .iter()
+                .enumerate()
+                .find(|(_, price)| {
+                    coins
+                        .0
+                        .iter()
+                        .any(|coin| coin.denom == price.denom && !coin.amount.is_zero())
+                })
+                .or_else(|| prices.first().map(|price| (0, price)))
+                .ok_or_else(|| {
+                    StdError::generic_err(format!("Phase {} only accepts cw20 payment", phase.name))
+                })?;
+            let price = match price_override {
+                Some(amount) if index == 0 => amount * Uint128::from(quantity),
+                _ => option.amount,
+            };
+            let surplus = check_payment(&coins.0, &option.denom, price)?;
+            let paid = Payment::Native(Coin {
+                denom: option.denom.clone(),
+                amount: price,
+            });
+            (paid, surplus.into_iter().map(Payment::Native).collect())
+        }
+        Balance::Cw20(sent) => {
+            let cw20_price = phase
+                .cw20_prices
+                .iter()
+                .find(|price| price.address == sent.address.as_str())
+                .ok_or_else(|| {
+                    StdError::generic_err(format!("Unsupported payment token: {}", sent.address))
+                })?;
+            let price = cw20_price.amount * Uint128::from(quantity);
+            if sent.amount < price {
+                return Err(StdError::generic_err(format!(
+                    "Insufficient payment: expected {}{}, received {}{}",
+                    price, sent.address, sent.amount, sent.address
+                )));
+            }
+            let paid = Payment::Cw20(Cw20CoinVerified {
+                address: sent.address.clone(),
+                amount: price,
+            });
+            let mut surplus = vec![];
+            if sent.amount > price {
+                surplus.push(paid.with_amount(sent.amount - price));
+            }
+            (paid, surplus)
+        }
+    };
+
+    mint_counts(deps.storage, phase_id).save(recipient.as_bytes(), &(minted + quantity))?;
+    phase_supply(deps.storage).save(&phase_key, &(sold + Uint128::from(quantity)))?;
+
+    Ok((phase_id, paid, surplus))
+}
+
+/// Checks `addr` against the phase's allowlist. Returns its per-address cap, which is its own
+/// allocation when it has one and the phase's `mint_max` otherwise, and its price override.
+fn check_allowlist(
+    storage: &dyn Storage,
+    phase: &MintPhaseConfig,
+    addr: &Addr,
+    proof: Option<&MerkleProof>,
+) -> StdResult<(Option<Uint128>, Option<Uint128>)> {
+    match &phase.allowlist {
+        Some(Allowlist::Whitelist) => {
+            let entry = find_whitelist_entry(storage, addr)?
+                .ok_or_else(|| StdError::generic_err("Address is not whitelisted for minting."))?;
+            let mint_max = entry.mint_allocation.map(Uint128::from).or(phase.mint_max);
+            Ok((mint_max, entry.price))
+        }
+        Some(Allowlist::Merkle { root }) => {
+            let proof = proof
+                .ok_or_else(|| StdError::generic_err("A merkle proof is required in this phase."))?;
+            let leaf = merkle::leaf(addr.as_str(), proof.allocation);
+            if !merkle::verify(root, leaf, &proof.proof)? {
+                return Err(StdError::generic_err("Invalid merkle proof."));
+            }
+            Ok((proof.allocation.map(Uint128::from).or(phase.mint_max), None))
+        }
+        None => Ok((phase.mint_max, None)),
+    }
+}
+
+/// Phase at `time`, reporting `SoldOut` once every token is minted or reserved.
+fn current_phase(
+    storage: &dyn Storage,
+    config: &Config,
+    schedule: &[MintPhaseConfig],
+    time: u64,
+) -> StdResult<MintPhase> {
+    if config.closed {
+        return Ok(MintPhase::Closed);
+    }
+    if !config.is_mintable {
+        return Ok(MintPhase::NotStarted);
+    }
+    if committed_supply(storage, config)? >= config.nft_max_supply {
+        return Ok(MintPhase::SoldOut);
+    }
+
+    if let Some(id) = schedule.iter().position(|phase| phase.is_active_at(time)) {
+        return Ok(MintPhase::Active { id: id as u32 });
+    }
+    match schedule.iter().position(|phase| phase.start_time > time) {
+        Some(0) => Ok(MintPhase::NotStarted),
+        Some(_) => Ok(MintPhase::BetweenPhases),
+        None => Ok(MintPhase::Closed),
+    }
+}
+
+/// Phases must be ordered by start time and must not overlap.
+fn validate_phases(schedule: &[MintPhaseConfig]) -> StdResult<()> {
+    for (id, phase) in schedule.iter().enumerate() {
+        if phase.prices.is_empty() && phase.cw20_prices.is_empty() {
+            return Err(StdError::generic_err(format!(
+                "Phase {} must accept at least one payment option",
+                phase.name
+            )));
+        }
+        if let Some(Allowlist::Merkle { root }) = &phase.allowlist {
+            merkle::decode_hash(root)?;
+        }
+        if phase.price_mode != PriceMode::Fixed
+            && (phase.prices.len() != 1 || !phase.cw20_prices.is_empty())
+        {
+            return Err(StdError::generic_err(format!(
+                "Phase {} must have exactly one native price to use an auction or bonding curve",
+                phase.name
+            )));
+        }
+        match &phase.price_mode {
+            PriceMode::DutchAuction(auction)
+                if (auction.step_interval == 0 || auction.start_price < phase.prices[0].amount) => {
+                    return Err(StdError::generic_err(format!(
+                        "Auction phase {} must start above its floor and step at least every second",
+                        phase.name
+                    )));
+                }
+            PriceMode::BondingCurve(BondingCurve::Steps { steps })
+                if steps.windows(2).any(|pair| pair[0].from_supply >= pair[1].from_supply) => {
+                    return Err(StdError::generic_err(format!(
+                        "Price steps of phase {} must be ordered by supply",
+                        phase.name
+                    )));
+                }
+            _ => {}
+        }
+        if let Some(end_time) = phase.end_time {
+            if end_time <= phase.start_time {
+                return Err(StdError::generic_err(format!(
+                    "Phase {} must end after it starts",
+                    phase.name
+                )));
+            }
+        }
+        if let Some(next) = schedule.get(id + 1) {
+            if phase.end_time.is_none_or(|end_time| end_time > next.start_time) {
+                return Err(StdError::generic_err(format!(
+                    "Phase {} must end before phase {} starts",
+                    phase.name, next.name
+                )));
+            }
+        }
+    }
+    Ok(())
 }
 
-fn try_pause(deps: &mut Extern<DefaultApi, Storage, Querier>, env: Env) -> StdResult<CosmosMsg> {
-    let mut config = config(&mut deps.storage).load()?;
-    if config.owner != deps.api.canonical_address(&env.message.sender)? {
-        return Err(StdError::unauthorized());
+/// Tokens minted, pending or still held in the airdrop reserve.
+fn committed_supply(storage: &dyn Storage, config: &Config) -> StdResult<Uint128> {
+    Ok(sold_supply(storage)? + airdrop_remaining(storage, config)?)
+}
+
+/// Tokens minted plus those reserved by pending requests.
+fn sold_supply(storage: &dyn Storage) -> StdResult<Uint128> {
+    let minted = read_supply(storage).may_load()?.unwrap_or_default();
+    let pending = read_pending_token_requests(storage)
+        .may_load()?
+        .unwrap_or_default();
+    Ok(minted + Uint128::from(pending))
+}
+
+fn airdrop_remaining(storage: &dyn Storage, config: &Config) -> StdResult<Uint128> {
+    let airdropped = read_airdropped(storage).may_load()?.unwrap_or_default();
+    Ok(config.reserved_supply.saturating_sub(airdropped))
+}
+
+fn assert_supply_available(
+    storage: &dyn Storage,
+    config: &Config,
+    quantity: Uint128,
+) -> StdResult<()> {
+    if committed_supply(storage, config)? + quantity > config.nft_max_supply {
+        return Err(StdError::generic_err(format!(
+            "Sold out: all {} tokens have been minted or reserved",
+            config.nft_max_supply
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that `funds` cover `amount` of `denom`. Returns what is left over: the surplus of
+/// `denom` and every coin of any other denom.
+fn check_payment(funds: &[Coin], denom: &str, amount: Uint128) -> StdResult<Vec<Coin>> {
+    let paid = funds
+        .iter()
+        .filter(|coin| coin.denom == denom)
+        .fold(Uint128::zero(), |total, coin| total + coin.amount);
+    if paid < amount {
+        return Err(StdError::generic_err(format!(
+            "Insufficient payment: expected {}{}, received {}{}",
+            amount, denom, paid, denom
+        )));
+    }
+
+    let mut surplus: Vec<Coin> = vec![];
+    if paid > amount {
+        surplus.push(Coin {
+            denom: denom.to_string(),
+            amount: paid - amount,
+        });
+    }
+    for coin in funds.iter().filter(|coin| coin.denom != denom && !coin.amount.is_zero()) {
+        match surplus.iter_mut().find(|refund| refund.denom == coin.denom) {
+            Some(refund) => refund.amount += coin.amount,
+            None => surplus.push(coin.clone()),
+        }
+    }
+    Ok(surplus)
+}
+
+/// Sends `surplus` back to `recipient`, if there is any.
+fn refund_surplus(res: Response, recipient: &Addr, surplus: Vec<Payment>) -> StdResult<Response> {
+    if surplus.is_empty() {
+        return Ok(res);
+    }
+
+    let refund = surplus
+        .iter()
+        .map(|payment| payment.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    Ok(res
+        .add_messages(payment_msgs(recipient, surplus)?)
+        .add_attribute("refund", refund))
+}
+
+/// Messages paying `payments` to `recipient`, with all native coins in a single bank send.
+fn payment_msgs(recipient: &Addr, payments: Vec<Payment>) -> StdResult<Vec<CosmosMsg>> {
+    let mut native = vec![];
+    let mut messages = vec![];
+    for payment in payments {
+        match payment {
+            Payment::Native(coin) => native.push(coin),
+            Payment::Cw20(coin) => messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: coin.address.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: coin.amount,
+                })?,
+            })),
+        }
+    }
+    if !native.is_empty() {
+        messages.insert(
+            0,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: native,
+            }),
+        );
+    }
+    Ok(messages)
+}
+
+/// Adds `payment` to the funds held for pending token requests.
+fn hold_escrow(storage: &mut dyn Storage, payment: &Payment) -> StdResult<()> {
+    match payment {
+        Payment::Native(coin) => {
+            let held = read_escrow(storage)
+                .may_load(coin.denom.as_bytes())?
+                .unwrap_or_default();
+            escrow(storage).save(coin.denom.as_bytes(), &(held + coin.amount))
+        }
+        Payment::Cw20(coin) => {
+            let held = read_cw20_escrow(storage)
+                .may_load(coin.address.as_bytes())?
+                .unwrap_or_default();
+            cw20_escrow(storage).save(coin.address.as_bytes(), &(held + coin.amount))
+        }
+    }
+}
+
+/// Adds `payment` to the revenue in its denom.
+fn record_revenue(storage: &mut dyn Storage, payment: &Payment) -> StdResult<()> {
+    let key = payment.key();
+    let total = read_revenue(storage)
+        .may_load(&key)?
+        .map_or(Uint128::zero(), |total| total.amount());
+    revenue(storage).save(&key, &payment.with_amount(total + payment.amount()))
+}
+
+/// Splits the platform fee off `paid` for `quantity` tokens, recording the fee and the rest as
+/// revenue. Returns the fee if there is one to pay.
+fn charge_platform_fee(
+    storage: &mut dyn Storage,
+    paid: &Payment,
+    quantity: u32,
+) -> StdResult<Option<Payment>> {
+    let fee = match read_platform_fee(storage).may_load()? {
+        Some(platform_fee) => platform_fee.fee_for(paid, quantity)?,
+        None => paid.with_amount(Uint128::zero()),
+    };
+    record_revenue(storage, &paid.with_amount(paid.amount() - fee.amount()))?;
+    if fee.amount().is_zero() {
+        return Ok(None);
+    }
+
+    let key = fee.key();
+    let total = read_platform_fees(storage)
+        .may_load(&key)?
+        .map_or(Uint128::zero(), |total| total.amount());
+    platform_fees(storage).save(&key, &fee.with_amount(total + fee.amount()))?;
+    Ok(Some(fee))
+}
+
+/// Sends `fees` to the fee collector and reports them on `res`.
+fn pay_platform_fees(
+    storage: &dyn Storage,
+    res: Response,
+    fees: Vec<Payment>,
+) -> StdResult<Response> {
+    if fees.is_empty() {
+        return Ok(res);
+    }
+    let collector = read_platform_fee(storage).load()?.collector;
+    let charged = fees
+        .iter()
+        .map(|fee| fee.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    Ok(res
+        .add_messages(payment_msgs(&collector, fees)?)
+        .add_attribute("platform_fee", charged))
+}
+
+/// Releases `payment` once its request is fulfilled or refunded.
+fn release_escrow(storage: &mut dyn Storage, payment: &Payment) -> StdResult<()> {
+    match payment {
+        Payment::Native(coin) => {
+            let held = read_escrow(storage)
+                .may_load(coin.denom.as_bytes())?
+                .unwrap_or_default();
+            escrow(storage).save(coin.denom.as_bytes(), &held.checked_sub(coin.amount)?)
+        }
+        Payment::Cw20(coin) => {
+            let held = read_cw20_escrow(storage)
+                .may_load(coin.address.as_bytes())?
+                .unwrap_or_default();
+            cw20_escrow(storage).save(coin.address.as_bytes(), &held.checked_sub(coin.amount)?)
+        }
+    }
+}
+
+fn try_pause(deps: DepsMut, info: MessageInfo) -> StdResult<Response> {
+    let mut config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
     }
 
     config.paused = true;
-    config(&mut deps.storage).save(&config)?;
+    config.save(deps.storage)?;
 
-    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address,
-        funds: vec![],
-        msg: to_binary(&ExecuteMsg::Pause {})?,
-    }))
+    Ok(Response::new().add_attribute("action", "pause"))
 }
 
-fn try_unpause(deps: &mut Extern<DefaultApi, Storage, Querier>, env: Env) -> StdResult<CosmosMsg> {
-    let mut config = config(&mut deps.storage).load()?;
-    if config.owner != deps.api.canonical_address(&env.message.sender)? {
-        return Err(StdError::unauthorized());
+fn try_unpause(deps: DepsMut, info: MessageInfo) -> StdResult<Response> {
+    let mut config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
     }
 
     config.paused = false;
-    config(&mut deps.storage).save(&config)?;
+    config.save(deps.storage)?;
 
-    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address,
-        funds: vec![],
-        msg: to_binary(&ExecuteMsg::Unpause {})?,
-    }))
+    Ok(Response::new().add_attribute("action", "unpause"))
 }
 
-fn try_withdraw_fund(deps: &mut Extern<DefaultApi, Storage, Querier>, env: Env) -> StdResult<CosmosMsg> {
-    let config = config(&mut deps.storage).load()?;
-    if config.owner != deps.api.canonical_address(&env.message.sender)? {
-        return Err(StdError::unauthorized());
+fn try_close_mint(deps: DepsMut, info: MessageInfo) -> StdResult<Response> {
+    let mut config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
     }
 
-    // Perform fund withdrawal logic here...
+    config.closed = true;
+    config.save(deps.storage)?;
 
-    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: env.contract.address,
-        funds: vec![],
-        msg: to_binary(&ExecuteMsg::WithdrawFund {})?,
-    }))
+    Ok(Response::new().add_attribute("action", "close_mint"))
+}
+
+fn try_withdraw_fund(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    // Once ownership is renounced the payees are fixed, so anyone may pay them out.
+    if !config.owner.is_empty() && info.sender != config.owner {
+        return Err(unauthorized());
+    }
+
+    // Everything except the payments held for pending requests belongs to the owner.
+    let mut payouts = vec![];
+    for coin in deps.querier.query_all_balances(&env.contract.address)? {
+        let held = read_escrow(deps.storage)
+            .may_load(coin.denom.as_bytes())?
+            .unwrap_or_default();
+        let amount = coin.amount.saturating_sub(held);
+        if !amount.is_zero() {
+            payouts.push(Payment::Native(Coin {
+                denom: coin.denom,
+                amount,
+            }));
+        }
+    }
+    let tokens = read_cw20_escrow(deps.storage)
+        .range(None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (token, held) in tokens {
+        let address = deps.api.addr_validate(
+            &String::from_utf8(token).map_err(|_| StdError::generic_err("Invalid cw20 key"))?,
+        )?;
+        let balance: BalanceResponse = deps.querier.query_wasm_smart(
+            &address,
+            &Cw20QueryMsg::Balance {
+                address: env.contract.address.to_string(),
+            },
+        )?;
+        let amount = balance.balance.saturating_sub(held);
+        if !amount.is_zero() {
+            payouts.push(Payment::Cw20(Cw20CoinVerified { address, amount }));
+        }
+    }
+    if payouts.is_empty() {
+        return Err(StdError::generic_err("No funds to withdraw"));
+    }
+
+    let withdrawn = payouts
+        .iter()
+        .map(|payment| payment.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let mut res = Response::new()
+        .add_attribute("action", "withdraw_fund")
+        .add_attribute("amount", withdrawn);
+
+    // Split each payout by share, with rounding dust going to the last payee.
+    let payees = load_payees(deps.as_ref())?;
+    let mut remaining: Vec<Uint128> = payouts.iter().map(Payment::amount).collect();
+    for (n, payee) in payees.iter().enumerate() {
+        let mut shares = vec![];
+        for (payout, left) in payouts.iter().zip(remaining.iter_mut()) {
+            let amount = if n == payees.len() - 1 {
+                *left
+            } else {
+                payout.amount().multiply_ratio(payee.share_bps, 10_000u32)
+            };
+            *left -= amount;
+            if !amount.is_zero() {
+                shares.push(payout.with_amount(amount));
+            }
+        }
+        res = res.add_messages(payment_msgs(&payee.address, shares)?);
+    }
+    Ok(res)
+}
+
+fn try_update_platform_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    fee: PlatformFee,
+) -> StdResult<Response> {
+    if read_platform_admin(deps.storage).may_load()?.as_ref() != Some(&info.sender) {
+        return Err(unauthorized());
+    }
+
+    let schedule = read_phases(deps.storage).load()?;
+    let fee = validate_platform_fee(deps.as_ref(), &schedule, fee)?;
+    platform_fee(deps.storage).save(&fee)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_platform_fee")
+        .add_attribute("bps", fee.bps.to_string())
+        .add_attribute("collector", fee.collector))
+}
+
+fn try_update_platform_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    admin: String,
+) -> StdResult<Response> {
+    if read_platform_admin(deps.storage).may_load()?.as_ref() != Some(&info.sender) {
+        return Err(unauthorized());
+    }
+
+    let admin = deps.api.addr_validate(&admin)?;
+    platform_admin(deps.storage).save(&admin)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_platform_admin")
+        .add_attribute("admin", admin))
+}
+
+fn validate_platform_fee(
+    deps: Deps,
+    schedule: &[MintPhaseConfig],
+    fee: PlatformFee,
+) -> StdResult<PlatformFee> {
+    if fee.bps > 10_000 {
+        return Err(StdError::generic_err("Platform fee cannot exceed 10000 bps"));
+    }
+    check_flat_fees(&fee, schedule)?;
+    Ok(PlatformFee {
+        collector: deps.api.addr_validate(fee.collector.as_str())?,
+        ..fee
+    })
+}
+
+/// Keeps every flat fee at or below what each phase charges per token in the same denom.
+fn check_flat_fees(fee: &PlatformFee, schedule: &[MintPhaseConfig]) -> StdResult<()> {
+    for phase in schedule {
+        let native = fee.flat_fees.iter().any(|flat| {
+            phase
+                .prices
+                .iter()
+                .any(|price| price.denom == flat.denom && flat.amount > price.amount)
+        });
+        let cw20 = fee.cw20_flat_fees.iter().any(|flat| {
+            phase
+                .cw20_prices
+                .iter()
+                .any(|price| price.address == flat.address && flat.amount > price.amount)
+        });
+        if native || cw20 {
+            return Err(StdError::generic_err(format!(
+                "Flat platform fee cannot exceed the price of phase {}",
+                phase.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The configured payees, or the owner alone if there are none.
+fn load_payees(deps: Deps) -> StdResult<Vec<Payee>> {
+    match read_payees(deps.storage).may_load()? {
+        Some(payees) => Ok(payees),
+        None => {
+            let config = read_config(deps.storage).load()?;
+            Ok(vec![Payee {
+                address: deps.api.addr_validate(&config.owner)?,
+                share_bps: 10_000,
+            }])
+        }
+    }
+}
+
+fn try_update_payees(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_payees: Vec<Payee>,
+) -> StdResult<Response> {
+    let config = config(deps.storage).load()?;
+    if info.sender != config.owner {
+        return Err(unauthorized());
+    }
+
+    let mut total = 0u32;
+    for payee in new_payees.iter() {
+        if payee.share_bps == 0 || payee.share_bps > 10_000 {
+            return Err(StdError::generic_err(format!(
+                "Payee share must be between 1 and 10000 bps, got {}",
+                payee.share_bps
+            )));
+        }
+        total = total
+            .checked_add(payee.share_bps)
+            .ok_or_else(|| StdError::generic_err("Payee shares overflow"))?;
+    }
+    if total != 10_000 {
+        return Err(StdError::generic_err(format!(
+            "Payee shares must add up to 10000 bps, got {}",
+            total
+        )));
+    }
+    for payee in new_payees.iter() {
+        deps.api.addr_validate(payee.address.as_str())?;
+    }
+    payees(deps.storage).save(&new_payees)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_payees")
+        .add_attribute("payees", new_payees.len().to_string()))
 }
 
 pub fn query(
-    deps: &Extern<DefaultApi, Storage, Querier>,
+    deps: Deps,
+    env: Env,
     msg: QueryMsg,
-) -> StdResult<Response> {
+) -> StdResult<Binary> {
     match msg {
-        QueryMsg::Config {} => to_binary(&query_config(deps)?),
-        QueryMsg::IsWhitelisted { addr } => to_binary(&query_is_whitelisted(deps, addr)?),
-        QueryMsg::WhitelistSize {} => to_binary(&query_whitelist_size(deps)?),
-        QueryMsg::TokenRequestsCount {} => to_binary(&query_token_requests_count(deps)?),
-        QueryMsg::CurrentSupply {} => to_binary(&query_current_supply(deps)?),
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::IsWhitelisted { addr } => to_json_binary(&query_is_whitelisted(deps, env, addr)?),
+        QueryMsg::WhitelistSize {} => to_json_binary(&query_whitelist_size(deps)?),
+        QueryMsg::WhitelistEntries { start_after, limit } => {
+            to_json_binary(&query_whitelist_entries(deps, start_after, limit)?)
+        }
+        QueryMsg::TokenRequestsCount {} => to_json_binary(&query_token_requests_count(deps)?),
+        QueryMsg::CurrentSupply {} => to_json_binary(&query_current_supply(deps)?),
+        QueryMsg::MintStatus {} => to_json_binary(&query_mint_status(deps, env)?),
+        QueryMsg::Phases {} => to_json_binary(&query_phases(deps)?),
+        QueryMsg::PriceQuote { quantity } => to_json_binary(&query_price_quote(deps, env, quantity)?),
+        QueryMsg::PendingOwner {} => to_json_binary(&PendingOwnerResponse {
+            pending_owner: read_pending_owner(deps.storage).may_load()?,
+        }),
+        QueryMsg::Payees {} => to_json_binary(&PayeesResponse {
+            payees: load_payees(deps)?,
+        }),
+        QueryMsg::RoyaltyInfo {
+            token_id,
+            sale_price,
+        } => to_json_binary(&query_royalty_info(deps, token_id, sale_price)?),
+        QueryMsg::CheckRoyalties {} => to_json_binary(&CheckRoyaltiesResponse {
+            royalty_payments: true,
+        }),
+        QueryMsg::PlatformFee {} => to_json_binary(&query_platform_fee(deps)?),
+        QueryMsg::Revenue {} => to_json_binary(&query_revenue(deps)?),
+        QueryMsg::VoucherUsage { nonce } => to_json_binary(&query_voucher_usage(deps, nonce)?),
+        QueryMsg::VerifyMerkleProof {
+            phase_id,
+            addr,
+            proof,
+        } => to_json_binary(&query_verify_merkle_proof(deps, phase_id, addr, proof)?),
+        QueryMsg::MintAllocation { addr, proof } => {
+            to_json_binary(&query_mint_allocation(deps, env, addr, proof)?)
+        }
         QueryMsg::TokenRequestByIndex { index } => {
-            to_binary(&query_token_request_by_index(deps, index)?)
+            to_json_binary(&query_token_request_by_index(deps, env, index)?)
         }
     }
 }
 
-fn query_config(deps: &Extern<DefaultApi, Storage, Querier>) -> StdResult<ConfigResponse> {
-    let config = config(&deps.storage).load()?;
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = read_config(deps.storage).load()?;
     Ok(ConfigResponse {
         minter: config.minter,
         nft_addr: config.nft_addr,
@@ -289,38 +2192,321 @@ fn query_config(deps: &Extern<DefaultApi, Storage, Querier>) -> StdResult<Config
         whitelist_mint_period: config.whitelist_mint_period,
         whitelist_mint_price_amount: config.whitelist_mint_price_amount,
         paused: config.paused,
+        request_expiry: config.request_expiry,
+        closed: config.closed,
+        voucher_signer: config.voucher_signer,
+        max_per_tx: config.max_per_tx,
+        reserved_supply: config.reserved_supply,
     })
 }
 
 fn query_is_whitelisted(
-    deps: &Extern<DefaultApi, Storage, Querier>,
-    addr: Addr,
+    deps: Deps,
+    env: Env,
+    addr: String,
 ) -> StdResult<IsWhitelistedResponse> {
-    let whitelist = whitelist(&deps.storage);
-    let is_whitelisted = whitelist.is_whitelisted(&addr)?;
-    Ok(IsWhitelistedResponse { is_whitelisted })
+    let addr = deps.api.addr_validate(&addr)?;
+    let entry = match find_whitelist_entry(deps.storage, &addr)? {
+        Some(entry) => entry,
+        None => {
+            return Ok(IsWhitelistedResponse {
+                is_whitelisted: false,
+                remaining_allocation: None,
+            })
+        }
+    };
+
+    // Measure the allocation against the open phase, if there is one.
+    let config = read_config(deps.storage).load()?;
+    let schedule = read_phases(deps.storage).load()?;
+    let remaining_allocation = match current_phase(deps.storage, &config, &schedule, env.block.time.seconds())? {
+        MintPhase::Active { id } => {
+            let minted = read_mint_counts(deps.storage, id)
+                .may_load(addr.as_bytes())?
+                .unwrap_or_default();
+            entry
+                .mint_allocation
+                .map(Uint128::from)
+                .or(schedule[id as usize].mint_max)
+                .map(|mint_max| mint_max.saturating_sub(Uint128::from(minted)))
+        }
+        _ => entry.mint_allocation.map(Uint128::from),
+    };
+
+    Ok(IsWhitelistedResponse {
+        is_whitelisted: true,
+        remaining_allocation,
+    })
 }
 
-fn query_whitelist_size(deps: &Extern<DefaultApi, Storage, Querier>) -> StdResult<WhitelistSizeResponse> {
-    let whitelist = whitelist(&deps.storage);
-    let whitelist_size = whitelist.whitelist_size()?;
+fn query_whitelist_size(deps: Deps) -> StdResult<WhitelistSizeResponse> {
+    let whitelist_size = read_whitelist_size(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
     Ok(WhitelistSizeResponse { whitelist_size })
 }
 
-fn query_token_requests_count(deps: &Extern<DefaultApi, Storage, Querier>) -> StdResult<TokenRequestsCountResponse> {
-    // Perform token requests count logic here...
-    Ok(TokenRequestsCountResponse { token_requests_count: "TODO".to_string() })
+fn query_whitelist_entries(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<WhitelistEntriesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // Appending a zero byte makes the range start strictly after `start_after`.
+    let start = start_after.map(|addr| {
+        let mut key = addr.into_bytes();
+        key.push(0);
+        key
+    });
+
+    let generation = read_whitelist_generation(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let entries = read_whitelist_entries(deps.storage, generation)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, entry) = item?;
+            let addr = String::from_utf8(key)
+                .map_err(|_| StdError::generic_err("Invalid whitelist key"))?;
+            Ok(WhitelistEntryResponse { addr, entry })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(WhitelistEntriesResponse { entries })
+}
+
+fn query_mint_status(
+    deps: Deps,
+    env: Env,
+) -> StdResult<MintStatusResponse> {
+    let config = read_config(deps.storage).load()?;
+    let schedule = read_phases(deps.storage).load()?;
+    let phase = current_phase(deps.storage, &config, &schedule, env.block.time.seconds())?;
+
+    let active = match phase {
+        MintPhase::Active { id } => schedule.get(id as usize),
+        _ => None,
+    };
+    let next_phase_time = match phase {
+        MintPhase::NotStarted if !config.is_mintable => None,
+        MintPhase::NotStarted | MintPhase::BetweenPhases | MintPhase::Active { .. } => schedule
+            .iter()
+            .map(|phase| phase.start_time)
+            .find(|start_time| *start_time > env.block.time.seconds())
+            .or_else(|| active.and_then(|phase| phase.end_time)),
+        MintPhase::SoldOut | MintPhase::Closed => None,
+    };
+
+    Ok(MintStatusResponse {
+        phase,
+        phase_name: active.map(|phase| phase.name.clone()),
+        next_phase_in: next_phase_time.map(|time| time - env.block.time.seconds()),
+        prices: match active {
+            Some(phase) => batch_prices(deps.storage, phase, env.block.time.seconds(), 1)?,
+            None => vec![],
+        },
+        cw20_prices: active
+            .map(|phase| phase.cw20_prices.clone())
+            .unwrap_or_default(),
+    })
+}
+
+fn query_phases(deps: Deps) -> StdResult<PhasesResponse> {
+    let phases = read_phases(deps.storage).load()?;
+    Ok(PhasesResponse { phases })
 }
 
-fn query_current_supply(deps: &Extern<DefaultApi, Storage, Querier>) -> StdResult<CurrentSupplyResponse> {
-    // Perform current supply logic here...
-    Ok(CurrentSupplyResponse { current_supply: "TODO".to_string() })
+fn query_price_quote(
+    deps: Deps,
+    env: Env,
+    quantity: u32,
+) -> StdResult<PriceQuoteResponse> {
+    let config = read_config(deps.storage).load()?;
+    let schedule = read_phases(deps.storage).load()?;
+    let phase = current_phase(deps.storage, &config, &schedule, env.block.time.seconds())?;
+
+    let (prices, cw20_prices) = match phase {
+        MintPhase::Active { id } => {
+            let active = &schedule[id as usize];
+            let prices = batch_prices(deps.storage, active, env.block.time.seconds(), quantity)?;
+            let cw20_prices = active
+                .cw20_prices
+                .iter()
+                .map(|price| Cw20Coin {
+                    address: price.address.clone(),
+                    amount: price.amount * Uint128::from(quantity),
+                })
+                .collect();
+            (prices, cw20_prices)
+        }
+        _ => (vec![], vec![]),
+    };
+    Ok(PriceQuoteResponse {
+        phase,
+        prices,
+        cw20_prices,
+    })
+}
+
+fn query_revenue(deps: Deps) -> StdResult<RevenueResponse> {
+    let revenue = read_revenue(deps.storage)
+        .range(None, None, Order::Ascending)
+        .map(|item| item.map(|(_, payment)| payment))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(RevenueResponse { revenue })
+}
+
+fn query_royalty_info(
+    deps: Deps,
+    token_id: String,
+    sale_price: Uint128,
+) -> StdResult<RoyaltiesInfoResponse> {
+    let royalty = match read_token_royalties(deps.storage).may_load(token_id.as_bytes())? {
+        Some(royalty) => royalty,
+        None => {
+            let config = read_config(deps.storage).load()?;
+            Royalty {
+                payment_address: config.royalty_payment_address,
+                percentage: config.royalty_percentage,
+            }
+        }
+    };
+    Ok(RoyaltiesInfoResponse {
+        address: royalty.payment_address,
+        royalty_amount: sale_price.multiply_ratio(royalty.percentage, 100u32),
+    })
+}
+
+fn query_platform_fee(deps: Deps) -> StdResult<PlatformFeeResponse> {
+    let collected = read_platform_fees(deps.storage)
+        .range(None, None, Order::Ascending)
+        .map(|item| item.map(|(_, payment)| payment))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(PlatformFeeResponse {
+        admin: read_platform_admin(deps.storage).may_load()?,
+        fee: read_platform_fee(deps.storage).may_load()?,
+        collected,
+    })
+}
+
+fn query_voucher_usage(
+    deps: Deps,
+    nonce: u64,
+) -> StdResult<VoucherUsageResponse> {
+    let used = read_voucher_nonces(deps.storage)
+        .may_load(&nonce.to_be_bytes())?
+        .unwrap_or_default();
+    Ok(VoucherUsageResponse { used })
+}
+
+fn query_verify_merkle_proof(
+    deps: Deps,
+    phase_id: u32,
+    addr: String,
+    proof: MerkleProof,
+) -> StdResult<VerifyMerkleProofResponse> {
+    let schedule = read_phases(deps.storage).load()?;
+    let root = match schedule.get(phase_id as usize) {
+        Some(MintPhaseConfig {
+            allowlist: Some(Allowlist::Merkle { root }),
+            ..
+        }) => root,
+        _ => {
+            return Err(StdError::generic_err(format!(
+                "Phase {} does not use a merkle allowlist",
+                phase_id
+            )))
+        }
+    };
+
+    let addr = deps.api.addr_validate(&addr)?;
+    let leaf = merkle::leaf(addr.as_str(), proof.allocation);
+    let valid = merkle::verify(root, leaf, &proof.proof)?;
+    Ok(VerifyMerkleProofResponse { valid })
+}
+
+fn query_mint_allocation(
+    deps: Deps,
+    env: Env,
+    addr: String,
+    proof: Option<MerkleProof>,
+) -> StdResult<MintAllocationResponse> {
+    let config = read_config(deps.storage).load()?;
+    let schedule = read_phases(deps.storage).load()?;
+    let addr = deps.api.addr_validate(&addr)?;
+    let phase = current_phase(deps.storage, &config, &schedule, env.block.time.seconds())?;
+    let (minted, mint_max) = match phase {
+        MintPhase::Active { id } => {
+            let minted = read_mint_counts(deps.storage, id)
+                .may_load(addr.as_bytes())?
+                .unwrap_or_default();
+            // Addresses that cannot mint in this phase have nothing left to mint.
+            let mint_max =
+                match check_allowlist(deps.storage, &schedule[id as usize], &addr, proof.as_ref()) {
+                    Ok((mint_max, _)) => mint_max,
+                    Err(_) => Some(Uint128::zero()),
+                };
+            (minted, mint_max)
+        }
+        _ => (0, Some(Uint128::zero())),
+    };
+    Ok(MintAllocationResponse {
+        phase,
+        minted,
+        mint_max,
+        remaining: mint_max.map(|mint_max| mint_max.saturating_sub(Uint128::from(minted))),
+    })
+}
+
+fn query_token_requests_count(deps: Deps) -> StdResult<TokenRequestsCountResponse> {
+    let token_requests_count = read_token_requests_count(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let pending_count = read_pending_token_requests(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    let refunded_count = read_refunded_token_requests(deps.storage)
+        .may_load()?
+        .unwrap_or_default();
+    Ok(TokenRequestsCountResponse {
+        token_requests_count,
+        pending_count,
+        refunded_count,
+    })
+}
+
+fn query_current_supply(deps: Deps) -> StdResult<CurrentSupplyResponse> {
+    let config = read_config(deps.storage).load()?;
+    let current_supply = read_supply(deps.storage).may_load()?.unwrap_or_default();
+    let pending_supply = Uint128::from(
+        read_pending_token_requests(deps.storage)
+            .may_load()?
+            .unwrap_or_default(),
+    );
+    let airdrop_remaining = airdrop_remaining(deps.storage, &config)?;
+    Ok(CurrentSupplyResponse {
+        current_supply,
+        pending_supply,
+        airdrop_remaining,
+        max_supply: config.nft_max_supply,
+        sold_out: current_supply + pending_supply + airdrop_remaining >= config.nft_max_supply,
+    })
 }
 
 fn query_token_request_by_index(
-    deps: &Extern<DefaultApi, Storage, Querier>,
+    deps: Deps,
+    env: Env,
     index: Uint128,
 ) -> StdResult<TokenRequestByIndexResponse> {
-    // Perform token request by index logic here...
-    Ok(TokenRequestByIndexResponse { token_request: "TODO".to_string() })
+    let key = u64::try_from(index.u128())
+        .map_err(|_| StdError::generic_err("Token request index out of range"))?;
+    let token_request = TokenRequest::load(deps.storage, key)?;
+    let expired = token_request.status == TokenRequestStatus::Pending
+        && env.block.time.seconds() >= token_request.expires_at;
+    Ok(TokenRequestByIndexResponse {
+        index,
+        token_request,
+        expired,
+    })
 }
@@ -1,16 +1,20 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use crate::msg::Metadata;
+use crate::msg::{FulfillToken, MerkleProof, Metadata, MintVoucher, WhitelistAllocation};
+use crate::state::{MintPhaseConfig, Payee, PlatformFee, VoucherSigner};
 
 use cosmwasm_std::{
     Addr, Binary, CosmosMsg, Querier, QuerierWrapper, StdResult, Uint128, WasmMsg, WasmQuery,
-    CustomQuery, to_binary,
+    CustomQuery, to_json_binary,
 };
 
 use crate::msg::{ExecuteMsg, QueryMsg};
 use crate::msg::{
-    ConfigResponse, CurrentSupplyResponse, IsWhitelistedResponse, TokenRequestByIndexResponse,
-    TokenRequestsCountResponse, WhitelistSizeResponse,
+    ConfigResponse, CurrentSupplyResponse, IsWhitelistedResponse, MintAllocationResponse,
+    MintStatusResponse, PayeesResponse, PendingOwnerResponse, PhasesResponse, PlatformFeeResponse, PriceQuoteResponse,
+    RevenueResponse, RoyaltiesInfoResponse,
+    TokenRequestByIndexResponse, TokenRequestsCountResponse, VerifyMerkleProofResponse,
+    VoucherUsageResponse, WhitelistEntriesResponse, WhitelistSizeResponse,
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -22,7 +26,7 @@ impl FuryaBunkerMinterContract {
     }
 
     pub fn call<T: Into<ExecuteMsg>>(&self, msg: T) -> StdResult<CosmosMsg> {
-        let msg = to_binary(&msg.into())?;
+        let msg = to_json_binary(&msg.into())?;
         Ok(WasmMsg::Execute {
             contract_addr: self.addr().into(),
             msg,
@@ -39,7 +43,7 @@ impl FuryaBunkerMinterContract {
         let msg = QueryMsg::Config {};
         let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
-            msg: to_binary(&msg)?,
+            msg: to_json_binary(&msg)?,
         }
         .into();
         let res: ConfigResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
@@ -54,7 +58,7 @@ impl FuryaBunkerMinterContract {
         let msg = QueryMsg::IsWhitelisted { addr: addr.to_string() };
         let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
-            msg: to_binary(&msg)?,
+            msg: to_json_binary(&msg)?,
         }
         .into();
         let res: IsWhitelistedResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
@@ -69,13 +73,33 @@ impl FuryaBunkerMinterContract {
         let msg = QueryMsg::WhitelistSize {};
         let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
-            msg: to_binary(&msg)?,
+            msg: to_json_binary(&msg)?,
         }
         .into();
         let res: WhitelistSizeResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
         Ok(res)
     }
 
+    pub fn whitelist_entries<Q, CQ>(
+        &self,
+        querier: &Q,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<WhitelistEntriesResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::WhitelistEntries { start_after, limit };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: WhitelistEntriesResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
     pub fn token_requests_count<Q, CQ>(&self, querier: &Q) -> StdResult<TokenRequestsCountResponse>
     where
         Q: Querier,
@@ -84,7 +108,7 @@ impl FuryaBunkerMinterContract {
         let msg = QueryMsg::TokenRequestsCount {};
         let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
-            msg: to_binary(&msg)?,
+            msg: to_json_binary(&msg)?,
         }
         .into();
         let res: TokenRequestsCountResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
@@ -99,13 +123,170 @@ impl FuryaBunkerMinterContract {
         let msg = QueryMsg::CurrentSupply {};
         let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
-            msg: to_binary(&msg)?,
+            msg: to_json_binary(&msg)?,
         }
         .into();
         let res: CurrentSupplyResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
         Ok(res)
     }
 
+    pub fn mint_status<Q, CQ>(&self, querier: &Q) -> StdResult<MintStatusResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::MintStatus {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: MintStatusResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    pub fn phases<Q, CQ>(&self, querier: &Q) -> StdResult<PhasesResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::Phases {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: PhasesResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    pub fn price_quote<Q, CQ>(&self, querier: &Q, quantity: u32) -> StdResult<PriceQuoteResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::PriceQuote { quantity };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: PriceQuoteResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    pub fn royalty_info<Q, CQ>(
+        &self,
+        querier: &Q,
+        token_id: String,
+        sale_price: Uint128,
+    ) -> StdResult<RoyaltiesInfoResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::RoyaltyInfo {
+            token_id,
+            sale_price,
+        };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: RoyaltiesInfoResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    pub fn platform_fee<Q, CQ>(&self, querier: &Q) -> StdResult<PlatformFeeResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::PlatformFee {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: PlatformFeeResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    pub fn revenue<Q, CQ>(&self, querier: &Q) -> StdResult<RevenueResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::Revenue {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: RevenueResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    pub fn voucher_usage<Q, CQ>(&self, querier: &Q, nonce: u64) -> StdResult<VoucherUsageResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::VoucherUsage { nonce };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: VoucherUsageResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    pub fn verify_merkle_proof<Q, CQ>(
+        &self,
+        querier: &Q,
+        phase_id: u32,
+        addr: Addr,
+        proof: MerkleProof,
+    ) -> StdResult<VerifyMerkleProofResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::VerifyMerkleProof { phase_id, addr: addr.to_string(), proof };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: VerifyMerkleProofResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    pub fn mint_allocation<Q, CQ>(
+        &self,
+        querier: &Q,
+        addr: Addr,
+        proof: Option<MerkleProof>,
+    ) -> StdResult<MintAllocationResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::MintAllocation {
+            addr: addr.to_string(),
+            proof,
+        };
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: MintAllocationResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
     pub fn token_request_by_index<Q, CQ>(
         &self,
         querier: &Q,
@@ -118,7 +299,7 @@ impl FuryaBunkerMinterContract {
         let msg = QueryMsg::TokenRequestByIndex { index };
         let query = WasmQuery::Smart {
             contract_addr: self.addr().into(),
-            msg: to_binary(&msg)?,
+            msg: to_json_binary(&msg)?,
         }
         .into();
         let res: TokenRequestByIndexResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
@@ -129,15 +310,88 @@ impl FuryaBunkerMinterContract {
     where
         T: Into<String>,
     {
-        let msg = ExecuteMsg::UpdateConfig { minter: minter.map(Into::into) };
+        let msg = ExecuteMsg::UpdateConfig {
+            minter: minter.map(Into::into),
+            nft_addr: None,
+            nft_base_uri: None,
+            nft_max_supply: None,
+            nft_price_amount: None,
+            max_per_tx: None,
+        };
+        self.call(msg)
+    }
+
+    pub fn propose_owner<T>(&self, new_owner: T, expiry: Option<u64>) -> StdResult<CosmosMsg>
+    where
+        T: Into<String>,
+    {
+        let msg = ExecuteMsg::ProposeOwner {
+            new_owner: new_owner.into(),
+            expiry,
+        };
+        self.call(msg)
+    }
+
+    pub fn accept_ownership(&self) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::AcceptOwnership {};
+        self.call(msg)
+    }
+
+    pub fn cancel_ownership_transfer(&self) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::CancelOwnershipTransfer {};
+        self.call(msg)
+    }
+
+    pub fn renounce_ownership(&self) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::RenounceOwnership {};
         self.call(msg)
     }
 
+    pub fn pending_owner<Q, CQ>(&self, querier: &Q) -> StdResult<PendingOwnerResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::PendingOwner {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: PendingOwnerResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
     pub fn whitelist<T>(&self, addrs: Vec<T>) -> StdResult<CosmosMsg>
     where
         T: Into<String>,
     {
-        let msg = ExecuteMsg::Whitelist { addrs: addrs.into_iter().map(Into::into).collect() };
+        let msg = ExecuteMsg::Whitelist { addrs: addrs.into_iter().map(|addr| Addr::unchecked(addr)).collect() };
+        self.call(msg)
+    }
+
+    pub fn remove_from_whitelist<T>(&self, addrs: Vec<T>) -> StdResult<CosmosMsg>
+    where
+        T: Into<String>,
+    {
+        let msg = ExecuteMsg::RemoveFromWhitelist {
+            addrs: addrs.into_iter().map(|addr| Addr::unchecked(addr.into())).collect(),
+        };
+        self.call(msg)
+    }
+
+    pub fn clear_whitelist(&self) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::ClearWhitelist {};
+        self.call(msg)
+    }
+
+    pub fn set_whitelist_allocations(&self, allocations: Vec<WhitelistAllocation>) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::SetWhitelistAllocations { allocations };
+        self.call(msg)
+    }
+
+    pub fn migrate_whitelist(&self, limit: Option<u32>) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::MigrateWhitelist { limit };
         self.call(msg)
     }
 
@@ -146,11 +400,21 @@ impl FuryaBunkerMinterContract {
         self.call(msg)
     }
 
-    pub fn request_mint<T>(&self, addr: T) -> StdResult<CosmosMsg>
+    pub fn update_phases(&self, phases: Vec<MintPhaseConfig>) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdatePhases { phases };
+        self.call(msg)
+    }
+
+    pub fn request_mint<T>(
+        &self,
+        addr: T,
+        proof: Option<MerkleProof>,
+        quantity: Option<u32>,
+    ) -> StdResult<CosmosMsg>
     where
         T: Into<String>,
     {
-        let msg = ExecuteMsg::RequestMint { addr: addr.into() };
+        let msg = ExecuteMsg::RequestMint { addr: Addr::unchecked(addr), proof, quantity };
         self.call(msg)
     }
 
@@ -159,6 +423,8 @@ impl FuryaBunkerMinterContract {
         extension: Option<Metadata>,
         token_id: T,
         token_uri: Option<U>,
+        proof: Option<MerkleProof>,
+        quantity: Option<u32>,
     ) -> StdResult<CosmosMsg>
     where
         T: Into<String>,
@@ -169,10 +435,80 @@ impl FuryaBunkerMinterContract {
             extension,
             token_id: token_id.into(),
             token_uri: token_uri.map(Into::into),
+            proof,
+            quantity,
+        };
+        self.call(msg)
+    }
+
+    pub fn mint_with_voucher<T, U>(
+        &self,
+        voucher: MintVoucher,
+        signature: Binary,
+        extension: Option<Metadata>,
+        token_id: T,
+        token_uri: Option<U>,
+    ) -> StdResult<CosmosMsg>
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        let msg = ExecuteMsg::MintWithVoucher {
+            voucher,
+            signature,
+            extension,
+            token_id: token_id.into(),
+            token_uri: token_uri.map(Into::into),
+        };
+        self.call(msg)
+    }
+
+    pub fn claim_auction_rebate(&self, phase_id: u32) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::ClaimAuctionRebate { phase_id };
+        self.call(msg)
+    }
+
+    pub fn update_voucher_signer(&self, signer: Option<VoucherSigner>) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdateVoucherSigner { signer };
+        self.call(msg)
+    }
+
+    pub fn airdrop<T>(&self, recipients: Vec<(T, u32)>) -> StdResult<CosmosMsg>
+    where
+        T: Into<String>,
+    {
+        let msg = ExecuteMsg::Airdrop {
+            recipients: recipients
+                .into_iter()
+                .map(|(addr, amount)| (addr.into(), amount))
+                .collect(),
         };
         self.call(msg)
     }
 
+    pub fn update_merkle_root<T>(&self, phase_id: u32, root: T) -> StdResult<CosmosMsg>
+    where
+        T: Into<String>,
+    {
+        let msg = ExecuteMsg::UpdateMerkleRoot { phase_id, root: root.into() };
+        self.call(msg)
+    }
+
+    pub fn fulfill_requests(&self, tokens: Vec<FulfillToken>) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::FulfillRequests { tokens };
+        self.call(msg)
+    }
+
+    pub fn cancel_request(&self, index: Uint128) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::CancelRequest { index };
+        self.call(msg)
+    }
+
+    pub fn close_mint(&self) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::CloseMint {};
+        self.call(msg)
+    }
+
     pub fn pause(&self) -> StdResult<CosmosMsg> {
         let msg = ExecuteMsg::Pause {};
         self.call(msg)
@@ -183,6 +519,39 @@ impl FuryaBunkerMinterContract {
         self.call(msg)
     }
 
+    pub fn update_payees(&self, payees: Vec<Payee>) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdatePayees { payees };
+        self.call(msg)
+    }
+
+    pub fn payees<Q, CQ>(&self, querier: &Q) -> StdResult<PayeesResponse>
+    where
+        Q: Querier,
+        CQ: CustomQuery,
+    {
+        let msg = QueryMsg::Payees {};
+        let query = WasmQuery::Smart {
+            contract_addr: self.addr().into(),
+            msg: to_json_binary(&msg)?,
+        }
+        .into();
+        let res: PayeesResponse = QuerierWrapper::<CQ>::new(querier).query(&query)?;
+        Ok(res)
+    }
+
+    pub fn update_platform_fee(&self, fee: PlatformFee) -> StdResult<CosmosMsg> {
+        let msg = ExecuteMsg::UpdatePlatformFee { fee };
+        self.call(msg)
+    }
+
+    pub fn update_platform_admin<T>(&self, admin: T) -> StdResult<CosmosMsg>
+    where
+        T: Into<String>,
+    {
+        let msg = ExecuteMsg::UpdatePlatformAdmin { admin: admin.into() };
+        self.call(msg)
+    }
+
     pub fn withdraw_fund(&self) -> StdResult<CosmosMsg> {
         let msg = ExecuteMsg::WithdrawFund {};
         self.call(msg)
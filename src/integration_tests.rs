@@ -1,20 +1,40 @@
-use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{Addr, CosmosMsg, QuerierWrapper, StdResult, Uint128, WasmMsg};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    coin, coins, from_json, to_json_binary, Addr, BankMsg, Binary, CosmosMsg, Deps, Env, Event,
+    Reply, StdResult, Storage, SubMsgResponse, SubMsgResult, Uint128, WasmMsg, to_json_vec,
+};
+use serde::de::DeserializeOwned;
 
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{
+    execute, instantiate, migrate, reply, voucher_digest, INSTANTIATE_NFT_REPLY_ID,
+    MIGRATE_WHITELIST_BATCH,
+};
 use crate::msg::{
-    ConfigResponse, CurrentSupplyResponse, ExecuteMsg, InstantiateMsg, IsWhitelistedResponse,
-    QueryMsg, TokenRequestByIndexResponse, TokenRequestsCountResponse, WhitelistSizeResponse,
+    CheckRoyaltiesResponse, ConfigResponse, CurrentSupplyResponse, Cw721ExecuteMsg,
+    Cw721InstantiateMsg, ExecuteMsg, FulfillToken, InstantiateMsg, IsWhitelistedResponse,
+    MerkleProof, Metadata, MigrateMsg, MintAllocationResponse, MintStatusResponse, MintVoucher,
+    PayeesResponse, PendingOwnerResponse, PlatformFeeResponse, PriceQuoteResponse, QueryMsg,
+    ReceiveMsg, RevenueResponse, RoyaltiesInfoResponse, TokenRequestByIndexResponse,
+    TokenRequestsCountResponse, VerifyMerkleProofResponse, VoucherUsageResponse,
+    WhitelistAllocation, WhitelistEntriesResponse, WhitelistSizeResponse,
 };
-use crate::helpers::FuryaBunkerMinterContract;
-
-#[test]
-fn proper_initialization() {
-    let mut deps = mock_dependencies(&[]);
+use crate::merkle;
+use crate::state::{
+    state, whitelist, Allowlist, BondingCurve, DutchAuction, LegacyConfig, MintPhase,
+    MintPhaseConfig, Payee, Payment, PlatformFee, PriceMode, SignatureAlgorithm,
+    TokenRequestStatus, VoucherSigner, Whitelist,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{Cw20Coin, Cw20ExecuteMsg, Cw20ReceiveMsg};
+use ed25519_zebra::{SigningKey, VerificationKey};
+use sha2::{Digest, Sha256};
 
-    let msg = InstantiateMsg {
+/// Instantiate message shared by the tests, which override what they need.
+fn base_instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
         minter: String::from("minter"),
-        nft_addr: Addr::unchecked("nft_address"),
+        nft_ci: 1,
+        nft_name: String::from("Bunker"),
         nft_base_uri: String::from("base_uri"),
         nft_max_supply: Uint128::from(1000u128),
         nft_price_amount: Uint128::from(100u128),
@@ -29,41 +49,78 @@ fn proper_initialization() {
         whitelist_mint_max: Uint128::from(50u128),
         whitelist_mint_period: 1000,
         whitelist_mint_price_amount: Uint128::from(50u128),
-    };
+        request_expiry: None,
+        max_per_tx: None,
+        reserved_supply: None,
+        platform_fee: None,
+        platform_admin: None,
+        voucher_signer: None,
+        phases: None,
+    }
+}
+
+/// Runs a query and decodes its response.
+fn query<T: DeserializeOwned>(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<T> {
+    from_json(crate::contract::query(deps, env, msg)?)
+}
+
+/// Reply to the cw721 instantiation submessage, as the chain would send it.
+fn nft_instantiated(addr: &str) -> Reply {
+    Reply {
+        id: INSTANTIATE_NFT_REPLY_ID,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![Event::new("instantiate").add_attribute("_contract_address", addr)],
+            data: None,
+        }),
+    }
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies();
+
+    let msg = base_instantiate_msg();
     let env = mock_env();
     let info = mock_info("creator", &[]);
     let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-    assert_eq!(0, res.messages.len());
+    assert_eq!(1, res.messages.len());
+    assert_eq!(res.messages[0].id, INSTANTIATE_NFT_REPLY_ID);
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Instantiate {
+            admin: Some(String::from("owner")),
+            code_id: 1,
+            msg: to_json_binary(&Cw721InstantiateMsg {
+                name: String::from("Bunker"),
+                symbol: String::from("NFT"),
+                minter: String::from(MOCK_CONTRACT_ADDR),
+            })
+            .unwrap(),
+            funds: vec![],
+            label: String::from("Bunker"),
+        })
+    );
+
+    // The collection address comes back in the reply
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+    let config: ConfigResponse = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    assert_eq!(config.nft_addr, Addr::unchecked("nft_address"));
 }
 
 #[test]
 fn update_config() {
-    let mut deps = mock_dependencies(&[]);
-    let contract_addr = Addr::unchecked("contract");
+    let mut deps = mock_dependencies();
     let caller = Addr::unchecked("caller");
 
     // Instantiate contract
     let instantiate_msg = InstantiateMsg {
-        minter: String::from("minter"),
-        nft_addr: Addr::unchecked("nft_address"),
-        nft_base_uri: String::from("base_uri"),
-        nft_max_supply: Uint128::from(1000u128),
-        nft_price_amount: Uint128::from(100u128),
         owner: caller.clone().into(),
-        is_mintable: true,
-        mint_max: Uint128::from(100u128),
-        mint_start_time: 0,
-        nft_symbol: String::from("NFT"),
-        price_denom: String::from("uusd"),
-        royalty_payment_address: String::from("royalty_address"),
-        royalty_percentage: 10,
-        whitelist_mint_max: Uint128::from(50u128),
-        whitelist_mint_period: 1000,
-        whitelist_mint_price_amount: Uint128::from(50u128),
+        ..base_instantiate_msg()
     };
     let instantiate_env = mock_env();
     let instantiate_info = mock_info("creator", &[]);
     let _res = instantiate(deps.as_mut(), instantiate_env, instantiate_info, instantiate_msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
 
     // Update config
     let update_config_msg = ExecuteMsg::UpdateConfig {
@@ -72,7 +129,7 @@ fn update_config() {
         nft_base_uri: Some(String::from("new_base_uri")),
         nft_max_supply: Some(Uint128::from(2000u128)),
         nft_price_amount: Some(Uint128::from(200u128)),
-        owner: Some(String::from("new_owner")),
+        max_per_tx: None,
     };
     let update_config_env = mock_env();
     let update_config_info = mock_info(caller.as_str(), &[]);
@@ -87,8 +144,8 @@ fn update_config() {
     assert_eq!(query_config_response.nft_base_uri, "new_base_uri");
     assert_eq!(query_config_response.nft_max_supply, Uint128::from(2000u128));
     assert_eq!(query_config_response.nft_price_amount, Uint128::from(200u128));
-    assert_eq!(query_config_response.owner, "new_owner");
-    assert_eq!(query_config_response.is_mintable, true);
+    assert_eq!(query_config_response.owner, "caller");
+    assert!(query_config_response.is_mintable);
     assert_eq!(query_config_response.mint_max, Uint128::from(100u128));
     assert_eq!(query_config_response.mint_start_time, 0);
     assert_eq!(query_config_response.nft_symbol, "NFT");
@@ -98,7 +155,1883 @@ fn update_config() {
     assert_eq!(query_config_response.whitelist_mint_max, Uint128::from(50u128));
     assert_eq!(query_config_response.whitelist_mint_period, 1000);
     assert_eq!(query_config_response.whitelist_mint_price_amount, Uint128::from(50u128));
-    assert_eq!(query_config_response.paused, false);
+    assert!(!query_config_response.paused);
+}
+
+#[test]
+fn request_mint_is_queued() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    // Short payment is rejected
+    let request_msg = ExecuteMsg::RequestMint {
+        addr: Addr::unchecked("buyer"),
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(99, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, request_msg.clone()).unwrap_err();
+
+    // Wrong denom is rejected
+    let info = mock_info("buyer", &coins(100, "ufury"));
+    execute(deps.as_mut(), mock_env(), info, request_msg.clone()).unwrap_err();
+
+    // The phase is capped, so nobody can use up another wallet's allocation
+    let info = mock_info("stranger", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, request_msg.clone()).unwrap_err();
+
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), info, request_msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    let count: TokenRequestsCountResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::TokenRequestsCount {}).unwrap();
+    assert_eq!(count.token_requests_count, 1);
+
+    let request: TokenRequestByIndexResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::TokenRequestByIndex { index: Uint128::zero() },
+    )
+    .unwrap();
+    assert_eq!(request.token_request.requester, Addr::unchecked("buyer"));
+    assert_eq!(request.token_request.paid.amount(), Uint128::from(100u128));
+    assert_eq!(request.token_request.phase_id, 0);
+    assert_eq!(request.token_request.status, TokenRequestStatus::Pending);
+}
+
+#[test]
+fn minter_fulfills_pending_requests() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    for buyer in ["alice", "bob"] {
+        let request_msg = ExecuteMsg::RequestMint {
+            addr: Addr::unchecked(buyer),
+            proof: None,
+            quantity: None,
+        };
+        let info = mock_info(buyer, &coins(100, "uusd"));
+        execute(deps.as_mut(), mock_env(), info, request_msg).unwrap();
+    }
+
+    let fulfill_msg = ExecuteMsg::FulfillRequests {
+        tokens: vec![FulfillToken {
+            token_id: String::from("1"),
+            token_uri: None,
+            extension: None,
+        }],
+    };
+
+    // Only the minter may fulfill
+    let info = mock_info("alice", &[]);
+    execute(deps.as_mut(), mock_env(), info, fulfill_msg.clone()).unwrap_err();
+
+    let info = mock_info("minter", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, fulfill_msg).unwrap();
+    assert_eq!(1, res.messages.len());
+
+    let request: TokenRequestByIndexResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::TokenRequestByIndex { index: Uint128::zero() },
+    )
+    .unwrap();
+    assert_eq!(
+        request.token_request.status,
+        TokenRequestStatus::Fulfilled { token_id: String::from("1") }
+    );
+
+    let supply: CurrentSupplyResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::CurrentSupply {}).unwrap();
+    assert_eq!(supply.current_supply, Uint128::new(1));
+
+    // Asking for more tokens than there are pending requests fails
+    let fulfill_msg = ExecuteMsg::FulfillRequests {
+        tokens: vec![
+            FulfillToken { token_id: String::from("2"), token_uri: None, extension: None },
+            FulfillToken { token_id: String::from("3"), token_uri: None, extension: None },
+        ],
+    };
+    let info = mock_info("minter", &[]);
+    execute(deps.as_mut(), mock_env(), info, fulfill_msg).unwrap_err();
+}
+
+#[test]
+fn expired_request_can_be_refunded() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        request_expiry: Some(3600),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let request_msg = ExecuteMsg::RequestMint {
+        addr: Addr::unchecked("buyer"),
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, request_msg).unwrap();
+
+    let cancel_msg = ExecuteMsg::CancelRequest { index: Uint128::zero() };
+
+    // Not expired yet
+    let info = mock_info("buyer", &[]);
+    execute(deps.as_mut(), mock_env(), info, cancel_msg.clone()).unwrap_err();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(3600);
+
+    // Only the requester may cancel
+    let info = mock_info("someone", &[]);
+    execute(deps.as_mut(), env.clone(), info, cancel_msg.clone()).unwrap_err();
+
+    let info = mock_info("buyer", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, cancel_msg.clone()).unwrap();
+    assert_eq!(1, res.messages.len());
+
+    let count: TokenRequestsCountResponse =
+        query(deps.as_ref(), env.clone(), QueryMsg::TokenRequestsCount {}).unwrap();
+    assert_eq!(count.pending_count, 0);
+    assert_eq!(count.refunded_count, 1);
+
+    // A refunded request cannot be refunded twice
+    let info = mock_info("buyer", &[]);
+    execute(deps.as_mut(), env, info, cancel_msg).unwrap_err();
+}
+
+#[test]
+fn pending_requests_count_toward_max_supply() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        nft_max_supply: Uint128::from(2u128),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let request_msg = ExecuteMsg::RequestMint {
+        addr: Addr::unchecked("buyer"),
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, request_msg).unwrap();
+
+    let mint_msg = ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("1"),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap();
+
+    let supply: CurrentSupplyResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::CurrentSupply {}).unwrap();
+    assert_eq!(supply.current_supply, Uint128::new(1));
+    assert_eq!(supply.pending_supply, Uint128::new(1));
+    assert!(supply.sold_out);
+
+    let mint_msg = ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("2"),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    let err = execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap_err();
+    assert!(err.to_string().contains("Sold out"));
+}
+
+#[test]
+fn per_address_mint_cap() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        mint_max: Uint128::from(2u128),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mint_msg = ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("1"),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap();
+
+    // Requests count toward the same allocation
+    let request_msg = ExecuteMsg::RequestMint {
+        addr: Addr::unchecked("buyer"),
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, request_msg.clone()).unwrap();
+
+    let allocation: MintAllocationResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::MintAllocation { addr: String::from("buyer"), proof: None },
+    )
+    .unwrap();
+    assert_eq!(allocation.phase, MintPhase::Active { id: 0 });
+    assert_eq!(allocation.minted, 2);
+    assert_eq!(allocation.remaining, Some(Uint128::zero()));
+
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    let err = execute(deps.as_mut(), mock_env(), info, request_msg).unwrap_err();
+    assert!(err.to_string().contains("Mint limit reached"));
+}
+
+#[test]
+fn mint_phases_follow_block_time() {
+    let mut deps = mock_dependencies();
+    let now = mock_env().block.time.seconds();
+
+    let msg = InstantiateMsg {
+        mint_start_time: now + 100,
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let status: MintStatusResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::MintStatus {}).unwrap();
+    assert_eq!(status.phase, MintPhase::NotStarted);
+    assert_eq!(status.next_phase_in, Some(100));
+    assert!(status.prices.is_empty());
+
+    let mint_msg = ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("1"),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap_err();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    let status: MintStatusResponse =
+        query(deps.as_ref(), env.clone(), QueryMsg::MintStatus {}).unwrap();
+    assert_eq!(status.phase, MintPhase::Active { id: 0 });
+    assert_eq!(status.phase_name, Some(String::from("whitelist")));
+    assert_eq!(status.next_phase_in, Some(1000));
+    assert_eq!(status.prices, coins(50, "uusd"));
+
+    env.block.time = env.block.time.plus_seconds(1000);
+    let status: MintStatusResponse =
+        query(deps.as_ref(), env, QueryMsg::MintStatus {}).unwrap();
+    assert_eq!(status.phase, MintPhase::Active { id: 1 });
+    assert_eq!(status.next_phase_in, None);
+    assert_eq!(status.prices, coins(100, "uusd"));
+
+    // Closing the mint is final
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::CloseMint {}).unwrap();
+    let status: MintStatusResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::MintStatus {}).unwrap();
+    assert_eq!(status.phase, MintPhase::Closed);
+}
+
+#[test]
+fn update_phases_only_changes_future_phases() {
+    let mut deps = mock_dependencies();
+    let now = mock_env().block.time.seconds();
+
+    let og = MintPhaseConfig {
+        name: String::from("og"),
+        start_time: now - 10,
+        end_time: Some(now + 100),
+        prices: coins(10, "uusd"),
+        cw20_prices: vec![],
+        price_mode: PriceMode::Fixed,
+        mint_max: Some(Uint128::new(1)),
+        supply_max: Some(Uint128::new(1)),
+        allowlist: None,
+    };
+    let public = MintPhaseConfig {
+        name: String::from("public"),
+        start_time: now + 100,
+        end_time: None,
+        prices: coins(100, "uusd"),
+        cw20_prices: vec![],
+        price_mode: PriceMode::Fixed,
+        mint_max: None,
+        supply_max: None,
+        allowlist: None,
+    };
+
+    let msg = InstantiateMsg {
+        phases: Some(vec![og.clone(), public.clone()]),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mint_msg = ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("1"),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("alice", &coins(10, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg.clone()).unwrap();
+
+    // The og phase only had one token to sell
+    let info = mock_info("bob", &coins(10, "uusd"));
+    let err = execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap_err();
+    assert!(err.to_string().contains("Phase og is sold out"));
+
+    // The running phase cannot be repriced
+    let mut repriced = og.clone();
+    repriced.prices = coins(1, "uusd");
+    let update_msg = ExecuteMsg::UpdatePhases { phases: vec![repriced, public.clone()] };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg).unwrap_err();
+
+    // A future phase can be
+    let mut cheaper = public;
+    cheaper.prices = coins(50, "uusd");
+    let update_msg = ExecuteMsg::UpdatePhases { phases: vec![og, cheaper] };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg).unwrap();
+}
+
+#[test]
+fn whitelist_entries_are_paginated() {
+    let mut deps = mock_dependencies();
+
+    let msg = base_instantiate_msg();
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let whitelist_msg = ExecuteMsg::Whitelist {
+        addrs: vec![
+            Addr::unchecked("carol"),
+            Addr::unchecked("alice"),
+            Addr::unchecked("bob"),
+            Addr::unchecked("alice"),
+        ],
+    };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, whitelist_msg).unwrap();
+
+    let size: WhitelistSizeResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::WhitelistSize {}).unwrap();
+    assert_eq!(size.whitelist_size, 3);
+
+    let page: WhitelistEntriesResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::WhitelistEntries { start_after: None, limit: Some(2) },
+    )
+    .unwrap();
+    let addrs: Vec<_> = page.entries.iter().map(|entry| entry.addr.as_str()).collect();
+    assert_eq!(addrs, vec!["alice", "bob"]);
+
+    let page: WhitelistEntriesResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::WhitelistEntries { start_after: Some(String::from("bob")), limit: None },
+    )
+    .unwrap();
+    let addrs: Vec<_> = page.entries.iter().map(|entry| entry.addr.as_str()).collect();
+    assert_eq!(addrs, vec!["carol"]);
+
+    let whitelisted: IsWhitelistedResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::IsWhitelisted { addr: String::from("bob") },
+    )
+    .unwrap();
+    assert!(whitelisted.is_whitelisted);
+}
+
+#[test]
+fn legacy_whitelist_counts_while_migrating() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        mint_start_time: mock_env().block.time.seconds(),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mut legacy = Whitelist::new();
+    legacy.addresses.push(Addr::unchecked("early"));
+    legacy.addresses.push(Addr::unchecked("earlier"));
+    whitelist(&mut deps.storage).save(&legacy).unwrap();
+
+    // Only one address has been moved over, but both can mint in the whitelist phase
+    let migrate_msg = ExecuteMsg::MigrateWhitelist { limit: Some(1) };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, migrate_msg).unwrap();
+    for buyer in ["early", "earlier"] {
+        let mint_msg = ExecuteMsg::Mint {
+            extension: None,
+            token_id: String::from(buyer),
+            token_uri: None,
+            proof: None,
+            quantity: None,
+        };
+        let info = mock_info(buyer, &coins(50, "uusd"));
+        execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap();
+    }
+}
+
+#[test]
+fn remove_from_whitelist_covers_legacy_addresses() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        mint_start_time: mock_env().block.time.seconds(),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mut legacy = Whitelist::new();
+    legacy.addresses.push(Addr::unchecked("early"));
+    legacy.addresses.push(Addr::unchecked("earlier"));
+    whitelist(&mut deps.storage).save(&legacy).unwrap();
+
+    let remove_msg = ExecuteMsg::RemoveFromWhitelist {
+        addrs: vec![Addr::unchecked("early")],
+    };
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, remove_msg).unwrap();
+    assert!(res.attributes.iter().any(|attr| attr.key == "count" && attr.value == "1"));
+
+    let is_whitelisted = |deps: Deps, addr: &str| {
+        let whitelisted: IsWhitelistedResponse = query(
+            deps,
+            mock_env(),
+            QueryMsg::IsWhitelisted { addr: String::from(addr) },
+        )
+        .unwrap();
+        whitelisted.is_whitelisted
+    };
+    assert!(!is_whitelisted(deps.as_ref(), "early"));
+    assert!(is_whitelisted(deps.as_ref(), "earlier"));
+
+    // Migrating afterwards does not bring the removed address back
+    let migrate_msg = ExecuteMsg::MigrateWhitelist { limit: None };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, migrate_msg).unwrap();
+    assert!(!is_whitelisted(deps.as_ref(), "early"));
+    assert!(is_whitelisted(deps.as_ref(), "earlier"));
+}
+
+#[test]
+fn clear_whitelist_drops_legacy_whitelist() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        mint_start_time: mock_env().block.time.seconds(),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mut legacy = Whitelist::new();
+    legacy.addresses.push(Addr::unchecked("early"));
+    whitelist(&mut deps.storage).save(&legacy).unwrap();
+    let whitelist_msg = ExecuteMsg::Whitelist {
+        addrs: vec![Addr::unchecked("recent")],
+    };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, whitelist_msg).unwrap();
+
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::ClearWhitelist).unwrap();
+    assert!(whitelist(&mut deps.storage).may_load().unwrap().is_none());
+    for addr in ["early", "recent"] {
+        let whitelisted: IsWhitelistedResponse = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::IsWhitelisted { addr: String::from(addr) },
+        )
+        .unwrap();
+        assert!(!whitelisted.is_whitelisted);
+    }
+}
+
+#[test]
+fn whitelist_allocations_and_removal() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        mint_start_time: mock_env().block.time.seconds(),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let allocations_msg = ExecuteMsg::SetWhitelistAllocations {
+        allocations: vec![WhitelistAllocation {
+            addr: String::from("vip"),
+            mint_allocation: 3,
+            price: Some(Uint128::from(10u128)),
+        }],
+    };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, allocations_msg).unwrap();
+
+    // The custom price applies
+    let mint_msg = ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("1"),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("vip", &coins(10, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap();
+
+    let whitelisted: IsWhitelistedResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::IsWhitelisted { addr: String::from("vip") },
+    )
+    .unwrap();
+    assert!(whitelisted.is_whitelisted);
+    assert_eq!(whitelisted.remaining_allocation, Some(Uint128::new(2)));
+    let allocation: MintAllocationResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::MintAllocation { addr: String::from("vip"), proof: None },
+    )
+    .unwrap();
+    assert_eq!(allocation.mint_max, Some(Uint128::new(3)));
+    assert_eq!(allocation.remaining, Some(Uint128::new(2)));
+
+    let remove_msg = ExecuteMsg::RemoveFromWhitelist { addrs: vec![Addr::unchecked("vip")] };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, remove_msg).unwrap();
+
+    let whitelisted: IsWhitelistedResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::IsWhitelisted { addr: String::from("vip") },
+    )
+    .unwrap();
+    assert!(!whitelisted.is_whitelisted);
+
+    let whitelist_msg = ExecuteMsg::Whitelist {
+        addrs: vec![Addr::unchecked("alice"), Addr::unchecked("bob")],
+    };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, whitelist_msg).unwrap();
+
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::ClearWhitelist {}).unwrap();
+
+    let size: WhitelistSizeResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::WhitelistSize {}).unwrap();
+    assert_eq!(size.whitelist_size, 0);
+    let page: WhitelistEntriesResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::WhitelistEntries { start_after: None, limit: None },
+    )
+    .unwrap();
+    assert!(page.entries.is_empty());
+}
+
+#[test]
+fn merkle_allowlist_requires_valid_proof() {
+    let mut deps = mock_dependencies();
+
+    // Two leaf tree: alice with an allocation of 2, bob with the phase default
+    let alice = merkle::leaf("alice", Some(2));
+    let bob = merkle::leaf("bob", None);
+    let (first, second) = if alice <= bob { (alice, bob) } else { (bob, alice) };
+    let root = hex::encode(Sha256::new().chain_update(first).chain_update(second).finalize());
+
+    let msg = InstantiateMsg {
+        mint_start_time: mock_env().block.time.seconds(),
+        phases: Some(vec![MintPhaseConfig {
+            name: String::from("allowlist"),
+            start_time: mock_env().block.time.seconds(),
+            end_time: None,
+            prices: coins(50, "uusd"),
+            cw20_prices: vec![],
+            price_mode: PriceMode::Fixed,
+            mint_max: Some(Uint128::from(1u128)),
+            supply_max: None,
+            allowlist: Some(Allowlist::Merkle { root: root.clone() }),
+        }]),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let alice_proof = MerkleProof { proof: vec![hex::encode(bob)], allocation: Some(2) };
+    let verified: VerifyMerkleProofResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::VerifyMerkleProof {
+            phase_id: 0,
+            addr: String::from("alice"),
+            proof: alice_proof.clone(),
+        },
+    )
+    .unwrap();
+    assert!(verified.valid);
+
+    // Minting without a proof, or with a forged allocation, is rejected
+    for proof in [None,
+        Some(MerkleProof { proof: vec![hex::encode(bob)], allocation: Some(5) })] {
+        let mint_msg = ExecuteMsg::Mint {
+            extension: None,
+            token_id: String::from("1"),
+            token_uri: None,
+            proof,
+            quantity: None,
+        };
+        let info = mock_info("alice", &coins(50, "uusd"));
+        execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap_err();
+    }
+
+    // The allocation in the leaf overrides the phase mint_max
+    for token_id in ["1", "2", "3"].iter() {
+        let mint_msg = ExecuteMsg::Mint {
+            extension: None,
+            token_id: token_id.to_string(),
+            token_uri: None,
+            proof: Some(alice_proof.clone()),
+            quantity: None,
+        };
+        let info = mock_info("alice", &coins(50, "uusd"));
+        let res = execute(deps.as_mut(), mock_env(), info, mint_msg);
+        assert_eq!(res.is_ok(), *token_id != "3");
+    }
+
+    // Rotating the root invalidates old proofs
+    let update_msg = ExecuteMsg::UpdateMerkleRoot {
+        phase_id: 0,
+        root: hex::encode(bob),
+    };
+    let info = mock_info("buyer", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg.clone()).unwrap_err();
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg).unwrap();
+
+    let verified: VerifyMerkleProofResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::VerifyMerkleProof {
+            phase_id: 0,
+            addr: String::from("alice"),
+            proof: alice_proof,
+        },
+    )
+    .unwrap();
+    assert!(!verified.valid);
+}
+
+#[test]
+fn mint_with_signed_voucher() {
+    let mut deps = mock_dependencies();
+
+    let signing_key = SigningKey::from([7u8; 32]);
+    let pubkey: [u8; 32] = VerificationKey::from(&signing_key).into();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        mint_start_time: mock_env().block.time.seconds(),
+        voucher_signer: Some(VoucherSigner {
+            algorithm: SignatureAlgorithm::Ed25519,
+            pubkey: Binary::from(pubkey.to_vec()),
+        }),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let voucher = MintVoucher {
+        recipient: String::from("vip"),
+        price: coin(25, "uusd"),
+        max_quantity: 1,
+        expiry: mock_env().block.time.seconds() + 100,
+        nonce: 42,
+    };
+    let digest = voucher_digest(&mock_env().contract.address, &voucher).unwrap();
+    let signature: [u8; 64] = signing_key.sign(&digest).into();
+    let mint_msg = |voucher: MintVoucher, token_id: &str| ExecuteMsg::MintWithVoucher {
+        voucher,
+        signature: Binary::from(signature.to_vec()),
+        extension: None,
+        token_id: String::from(token_id),
+        token_uri: None,
+    };
+
+    // A tampered voucher does not match the signature
+    let mut tampered = voucher.clone();
+    tampered.max_quantity = 10;
+    let info = mock_info("relayer", &coins(25, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg(tampered, "1")).unwrap_err();
+
+    // Anyone may submit the voucher; the token goes to its recipient
+    let info = mock_info("relayer", &coins(25, "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), info, mint_msg(voucher.clone(), "1")).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("nft_address"),
+            funds: vec![],
+            msg: to_json_binary(&Cw721ExecuteMsg::Mint {
+                token_id: String::from("1"),
+                owner: String::from("vip"),
+                token_uri: None,
+                extension: Some(Metadata {
+                    royalty_payment_address: Some(String::from("royalty_address")),
+                    royalty_percentage: Some(10),
+                    ..Metadata::default()
+                }),
+            })
+            .unwrap(),
+        })
+    );
+
+    let usage: VoucherUsageResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::VoucherUsage { nonce: 42 }).unwrap();
+    assert_eq!(usage.used, 1);
+
+    // The nonce is spent, and an expired voucher is rejected regardless
+    let info = mock_info("relayer", &coins(25, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg(voucher.clone(), "2")).unwrap_err();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(101);
+    let info = mock_info("relayer", &coins(25, "uusd"));
+    execute(deps.as_mut(), env, info, mint_msg(voucher, "2")).unwrap_err();
+}
+
+#[test]
+fn batch_mint_checks_caps_for_the_whole_batch() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        mint_max: Uint128::from(5u128),
+        mint_start_time: mock_env().block.time.seconds(),
+        max_per_tx: Some(3),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mint_msg = |quantity: u32| ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("batch"),
+        token_uri: None,
+        proof: None,
+        quantity: Some(quantity),
+    };
+
+    // Over the per-transaction cap, or short of quantity times the price
+    let info = mock_info("buyer", &coins(400, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg(4)).unwrap_err();
+    let info = mock_info("buyer", &coins(200, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg(3)).unwrap_err();
+
+    let info = mock_info("buyer", &coins(300, "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), info, mint_msg(3)).unwrap();
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(
+        res.messages[2].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("nft_address"),
+            funds: vec![],
+            msg: to_json_binary(&Cw721ExecuteMsg::Mint {
+                token_id: String::from("batch-3"),
+                owner: String::from("buyer"),
+                token_uri: None,
+                extension: Some(Metadata {
+                    royalty_payment_address: Some(String::from("royalty_address")),
+                    royalty_percentage: Some(10),
+                    ..Metadata::default()
+                }),
+            })
+            .unwrap(),
+        })
+    );
+
+    // Three more would pass the per-wallet cap of five, so none are minted
+    let info = mock_info("buyer", &coins(300, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg(3)).unwrap_err();
+    let supply: CurrentSupplyResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::CurrentSupply {}).unwrap();
+    assert_eq!(supply.current_supply, Uint128::new(3));
+
+    // A batch request queues one record per token
+    let request_msg = ExecuteMsg::RequestMint {
+        addr: Addr::unchecked("buyer"),
+        proof: None,
+        quantity: Some(2),
+    };
+    let info = mock_info("buyer", &coins(200, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, request_msg).unwrap();
+    let count: TokenRequestsCountResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::TokenRequestsCount {}).unwrap();
+    assert_eq!(count.pending_count, 2);
+    let request: TokenRequestByIndexResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::TokenRequestByIndex { index: Uint128::new(1) },
+    )
+    .unwrap();
+    assert_eq!(request.token_request.paid, Payment::Native(coin(100, "uusd")));
+}
+
+#[test]
+fn airdrop_draws_from_reserved_supply() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        nft_max_supply: Uint128::from(5u128),
+        mint_start_time: mock_env().block.time.seconds(),
+        reserved_supply: Some(Uint128::from(2u128)),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    // The public can only reach the unreserved three tokens
+    let mint_msg = |quantity: u32| ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("public"),
+        token_uri: None,
+        proof: None,
+        quantity: Some(quantity),
+    };
+    let info = mock_info("buyer", &coins(400, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg(4)).unwrap_err();
+
+    // Nor can it take the token ids airdrops use
+    for (token_id, quantity) in [("reserved-1", 1u32), ("reserved", 2)] {
+        let msg = ExecuteMsg::Mint {
+            extension: None,
+            token_id: String::from(token_id),
+            token_uri: None,
+            proof: None,
+            quantity: Some(quantity),
+        };
+        let info = mock_info("buyer", &coins(100 * u128::from(quantity), "uusd"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(err.to_string().contains("reserved for airdrops"));
+    }
+
+    let info = mock_info("buyer", &coins(300, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg(3)).unwrap();
+
+    let status: MintStatusResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::MintStatus {}).unwrap();
+    assert_eq!(status.phase, MintPhase::SoldOut);
+
+    let airdrop_msg = ExecuteMsg::Airdrop {
+        recipients: vec![(String::from("team"), 1), (String::from("winner"), 1)],
+    };
+    let info = mock_info("buyer", &[]);
+    execute(deps.as_mut(), mock_env(), info, airdrop_msg.clone()).unwrap_err();
+
+    // Airdrops ignore pauses and need no payment
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap();
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, airdrop_msg.clone()).unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("nft_address"),
+            funds: vec![],
+            msg: to_json_binary(&Cw721ExecuteMsg::Mint {
+                token_id: String::from("reserved-2"),
+                owner: String::from("winner"),
+                token_uri: None,
+                extension: Some(Metadata {
+                    royalty_payment_address: Some(String::from("royalty_address")),
+                    royalty_percentage: Some(10),
+                    ..Metadata::default()
+                }),
+            })
+            .unwrap(),
+        })
+    );
+
+    let supply: CurrentSupplyResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::CurrentSupply {}).unwrap();
+    assert_eq!(supply.current_supply, Uint128::new(5));
+    assert_eq!(supply.airdrop_remaining, Uint128::zero());
+
+    // The reserve is used up
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, airdrop_msg).unwrap_err();
+}
+
+#[test]
+fn overpayment_and_stray_denoms_are_refunded() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        mint_start_time: mock_env().block.time.seconds(),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mint_msg = ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("1"),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &[coin(150, "uusd"), coin(7, "ufury")]);
+    let res = execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("buyer"),
+            amount: vec![coin(50, "uusd"), coin(7, "ufury")],
+        })
+    );
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "refund" && attr.value == "50uusd,7ufury"));
+
+    // Requests only record the exact charge
+    let request_msg = ExecuteMsg::RequestMint {
+        addr: Addr::unchecked("buyer"),
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(120, "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), info, request_msg).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("buyer"),
+            amount: coins(20, "uusd"),
+        })
+    );
+    let request: TokenRequestByIndexResponse = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::TokenRequestByIndex { index: Uint128::zero() },
+    )
+    .unwrap();
+    assert_eq!(request.token_request.paid, Payment::Native(coin(100, "uusd")));
+}
+
+#[test]
+fn cw20_payments_through_receive() {
+    let mut deps = mock_dependencies();
+    let now = mock_env().block.time.seconds();
+
+    let msg = InstantiateMsg {
+        mint_start_time: now,
+        request_expiry: Some(100),
+        phases: Some(vec![MintPhaseConfig {
+            name: String::from("public"),
+            start_time: now,
+            end_time: None,
+            prices: coins(100, "uusd"),
+            cw20_prices: vec![Cw20Coin {
+                address: String::from("usdc"),
+                amount: Uint128::from(5u128),
+            }],
+            price_mode: PriceMode::Fixed,
+            mint_max: None,
+            supply_max: None,
+            allowlist: None,
+        }]),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let receive_msg = |amount: u128, hook: &ReceiveMsg| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::from(amount),
+            msg: to_json_binary(hook).unwrap(),
+        })
+    };
+    let mint_hook = ReceiveMsg::Mint {
+        extension: None,
+        token_id: String::from("1"),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+
+    // Only the configured token is accepted, and it must cover the price
+    let info = mock_info("other_token", &[]);
+    execute(deps.as_mut(), mock_env(), info, receive_msg(5, &mint_hook)).unwrap_err();
+    let info = mock_info("usdc", &[]);
+    execute(deps.as_mut(), mock_env(), info, receive_msg(4, &mint_hook)).unwrap_err();
+
+    // The token is minted to the cw20 sender and the surplus goes back as a transfer
+    let info = mock_info("usdc", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, receive_msg(7, &mint_hook)).unwrap();
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("usdc"),
+            funds: vec![],
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("buyer"),
+                amount: Uint128::from(2u128),
+            })
+            .unwrap(),
+        })
+    );
+
+    // A cw20 request is refunded in the same token
+    let request_hook = ReceiveMsg::RequestMint {
+        addr: Addr::unchecked("buyer"),
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("usdc", &[]);
+    execute(deps.as_mut(), mock_env(), info, receive_msg(5, &request_hook)).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100);
+    let cancel_msg = ExecuteMsg::CancelRequest { index: Uint128::zero() };
+    let info = mock_info("buyer", &[]);
+    let res = execute(deps.as_mut(), env, info, cancel_msg).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("usdc"),
+            funds: vec![],
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("buyer"),
+                amount: Uint128::from(5u128),
+            })
+            .unwrap(),
+        })
+    );
+}
+
+#[test]
+fn withdraw_fund_keeps_pending_payments() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        mint_start_time: mock_env().block.time.seconds(),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mint_msg = ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("1"),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap();
+    let request_msg = ExecuteMsg::RequestMint {
+        addr: Addr::unchecked("buyer"),
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, request_msg).unwrap();
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(200, "uusd"));
+
+    let info = mock_info("buyer", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::WithdrawFund {}).unwrap_err();
+
+    // The pending request's payment stays in the contract
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::WithdrawFund {}).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("owner"),
+            amount: coins(100, "uusd"),
+        })
+    );
+}
+
+#[test]
+fn phases_accept_several_denoms() {
+    let mut deps = mock_dependencies();
+    let now = mock_env().block.time.seconds();
+
+    let msg = InstantiateMsg {
+        mint_start_time: now,
+        price_denom: String::from("ufury"),
+        phases: Some(vec![MintPhaseConfig {
+            name: String::from("public"),
+            start_time: now,
+            end_time: None,
+            prices: vec![coin(100, "ufury"), coin(5, "ibc/usdc")],
+            cw20_prices: vec![],
+            price_mode: PriceMode::Fixed,
+            mint_max: None,
+            supply_max: None,
+            allowlist: None,
+        }]),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let status: MintStatusResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::MintStatus {}).unwrap();
+    assert_eq!(status.prices, vec![coin(100, "ufury"), coin(5, "ibc/usdc")]);
+
+    let mint_msg = |token_id: &str| ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from(token_id),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "ufury"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg("1")).unwrap();
+    let info = mock_info("buyer", &coins(5, "ibc/usdc"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg("2")).unwrap();
+
+    // Not enough of the chosen denom
+    let info = mock_info("buyer", &coins(4, "ibc/usdc"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg("3")).unwrap_err();
+
+    let revenue: RevenueResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::Revenue {}).unwrap();
+    assert_eq!(
+        revenue.revenue,
+        vec![
+            Payment::Native(coin(5, "ibc/usdc")),
+            Payment::Native(coin(100, "ufury")),
+        ]
+    );
+}
+
+#[test]
+fn dutch_auction_rebates_settle_at_clearing_price() {
+    let mut deps = mock_dependencies();
+    let now = mock_env().block.time.seconds();
+
+    let msg = InstantiateMsg {
+        mint_start_time: now,
+        phases: Some(vec![MintPhaseConfig {
+            name: String::from("auction"),
+            start_time: now,
+            end_time: Some(now + 100),
+            prices: coins(40, "uusd"),
+            cw20_prices: vec![],
+            price_mode: PriceMode::DutchAuction(DutchAuction {
+                start_price: Uint128::from(100u128),
+                step: Uint128::from(20u128),
+                step_interval: 10,
+                rebate: true,
+            }),
+            mint_max: None,
+            supply_max: None,
+            allowlist: None,
+        }]),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mint_msg = |token_id: &str| ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from(token_id),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("early", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg("1")).unwrap();
+
+    // Two steps later the price has dropped to 60
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(25);
+    let status: MintStatusResponse =
+        query(deps.as_ref(), env.clone(), QueryMsg::MintStatus {}).unwrap();
+    assert_eq!(status.prices, coins(60, "uusd"));
+    let info = mock_info("late", &coins(59, "uusd"));
+    execute(deps.as_mut(), env.clone(), info, mint_msg("2")).unwrap_err();
+    let info = mock_info("late", &coins(60, "uusd"));
+    execute(deps.as_mut(), env.clone(), info, mint_msg("2")).unwrap();
+
+    let claim_msg = ExecuteMsg::ClaimAuctionRebate { phase_id: 0 };
+    let info = mock_info("early", &[]);
+    execute(deps.as_mut(), env.clone(), info, claim_msg.clone()).unwrap_err();
+
+    // Once the phase ends the early buyer gets back the difference to 60
+    env.block.time = env.block.time.plus_seconds(75);
+    let info = mock_info("early", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, claim_msg.clone()).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("early"),
+            amount: coins(40, "uusd"),
+        })
+    );
+    let info = mock_info("early", &[]);
+    execute(deps.as_mut(), env.clone(), info, claim_msg.clone()).unwrap_err();
+
+    // The late buyer paid the clearing price and has nothing to get back
+    let info = mock_info("late", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, claim_msg).unwrap();
+    assert!(res.messages.is_empty());
+
+    let revenue: RevenueResponse = query(deps.as_ref(), env, QueryMsg::Revenue {}).unwrap();
+    assert_eq!(revenue.revenue, vec![Payment::Native(coin(120, "uusd"))]);
+}
+
+#[test]
+fn dutch_auction_platform_fee_is_charged_on_clearing_price() {
+    let mut deps = mock_dependencies();
+    let now = mock_env().block.time.seconds();
+
+    let msg = InstantiateMsg {
+        mint_start_time: now,
+        phases: Some(vec![MintPhaseConfig {
+            name: String::from("auction"),
+            start_time: now,
+            end_time: Some(now + 100),
+            prices: coins(40, "uusd"),
+            cw20_prices: vec![],
+            price_mode: PriceMode::DutchAuction(DutchAuction {
+                start_price: Uint128::from(100u128),
+                step: Uint128::from(20u128),
+                step_interval: 10,
+                rebate: true,
+            }),
+            mint_max: None,
+            supply_max: None,
+            allowlist: None,
+        }]),
+        platform_fee: Some(PlatformFee {
+            collector: Addr::unchecked("platform"),
+            bps: 1000,
+            flat_fees: vec![],
+            cw20_flat_fees: vec![],
+        }),
+        platform_admin: Some(String::from("platform_admin")),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mint_msg = |token_id: &str| ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from(token_id),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("early", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg("1")).unwrap();
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(25);
+    let info = mock_info("late", &coins(60, "uusd"));
+    execute(deps.as_mut(), env.clone(), info, mint_msg("2")).unwrap();
+
+    // Both buyers pay the clearing price of 60, and the fee is charged on that
+    env.block.time = env.block.time.plus_seconds(75);
+    let claim_msg = ExecuteMsg::ClaimAuctionRebate { phase_id: 0 };
+    let info = mock_info("early", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, claim_msg.clone()).unwrap();
+    assert_eq!(
+        res.messages.iter().map(|msg| msg.msg.clone()).collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("early"),
+                amount: coins(40, "uusd"),
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("platform"),
+                amount: coins(6, "uusd"),
+            }),
+        ]
+    );
+    let info = mock_info("late", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, claim_msg).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("platform"),
+            amount: coins(6, "uusd"),
+        })
+    );
+
+    let revenue: RevenueResponse = query(deps.as_ref(), env.clone(), QueryMsg::Revenue {}).unwrap();
+    assert_eq!(revenue.revenue, vec![Payment::Native(coin(108, "uusd"))]);
+
+    // Nothing is left in escrow once every bid has settled
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(108, "uusd"));
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), env, info, ExecuteMsg::WithdrawFund {}).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("owner"),
+            amount: coins(108, "uusd"),
+        })
+    );
+}
+
+#[test]
+fn bonding_curve_prices_follow_supply() {
+    let mut deps = mock_dependencies();
+    let now = mock_env().block.time.seconds();
+
+    let msg = InstantiateMsg {
+        mint_start_time: now,
+        phases: Some(vec![MintPhaseConfig {
+            name: String::from("curve"),
+            start_time: now,
+            end_time: None,
+            prices: coins(10, "uusd"),
+            cw20_prices: vec![],
+            price_mode: PriceMode::BondingCurve(BondingCurve::Linear {
+                increment: Uint128::from(5u128),
+            }),
+            mint_max: None,
+            supply_max: None,
+            allowlist: None,
+        }]),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let quote: PriceQuoteResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::PriceQuote { quantity: 3 }).unwrap();
+    assert_eq!(quote.prices, coins(45, "uusd"));
+
+    let mint_msg = ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("curve"),
+        token_uri: None,
+        proof: None,
+        quantity: Some(2),
+    };
+    let info = mock_info("buyer", &coins(20, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg.clone()).unwrap_err();
+    let info = mock_info("buyer", &coins(25, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap();
+
+    // Requests move the curve on as well
+    let request_msg = ExecuteMsg::RequestMint {
+        addr: Addr::unchecked("buyer"),
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(20, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, request_msg).unwrap();
+
+    let quote: PriceQuoteResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::PriceQuote { quantity: 1 }).unwrap();
+    assert_eq!(quote.prices, coins(25, "uusd"));
+}
+
+#[test]
+fn withdraw_fund_splits_between_payees() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        mint_start_time: mock_env().block.time.seconds(),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    // Shares must add up to 100%
+    let update_msg = ExecuteMsg::UpdatePayees {
+        payees: vec![
+            Payee { address: Addr::unchecked("artist"), share_bps: 7000 },
+            Payee { address: Addr::unchecked("owner"), share_bps: 2000 },
+        ],
+    };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg).unwrap_err();
+    // Shares that would wrap around to 10000 are rejected too
+    let update_msg = ExecuteMsg::UpdatePayees {
+        payees: vec![
+            Payee { address: Addr::unchecked("artist"), share_bps: u32::MAX },
+            Payee { address: Addr::unchecked("owner"), share_bps: 10001 },
+        ],
+    };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg).unwrap_err();
+
+    let payees = vec![
+        Payee { address: Addr::unchecked("artist"), share_bps: 7000 },
+        Payee { address: Addr::unchecked("owner"), share_bps: 3000 },
+    ];
+    let update_msg = ExecuteMsg::UpdatePayees { payees: payees.clone() };
+    let info = mock_info("artist", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg.clone()).unwrap_err();
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg).unwrap();
+
+    let value: PayeesResponse = query(deps.as_ref(), mock_env(), QueryMsg::Payees {}).unwrap();
+    assert_eq!(value.payees, payees);
+
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(1001, "uusd"));
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::WithdrawFund {}).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("artist"),
+            amount: coins(700, "uusd"),
+        })
+    );
+    // The last payee picks up the rounding dust
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("owner"),
+            amount: coins(301, "uusd"),
+        })
+    );
+}
+
+#[test]
+fn platform_fee_goes_to_the_collector() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        mint_start_time: mock_env().block.time.seconds(),
+        platform_fee: Some(PlatformFee {
+            collector: Addr::unchecked("platform"),
+            bps: 500,
+            flat_fees: coins(1, "uusd"),
+            cw20_flat_fees: vec![],
+        }),
+        platform_admin: Some(String::from("platform_admin")),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mint_msg = ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("1"),
+        token_uri: None,
+        proof: None,
+        quantity: Some(2),
+    };
+    let info = mock_info("buyer", &coins(200, "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap();
+    // 5% of 200 plus 1 per token
+    assert_eq!(
+        res.messages[2].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("platform"),
+            amount: coins(12, "uusd"),
+        })
+    );
+    assert!(res.attributes.iter().any(|attr| attr.key == "platform_fee" && attr.value == "12uusd"));
+
+    let value: RevenueResponse = query(deps.as_ref(), mock_env(), QueryMsg::Revenue {}).unwrap();
+    assert_eq!(value.revenue, vec![Payment::Native(coin(188, "uusd"))]);
+    let value: PlatformFeeResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::PlatformFee {}).unwrap();
+    assert_eq!(value.collected, vec![Payment::Native(coin(12, "uusd"))]);
+
+    // Only the platform admin can change the fee
+    let update_msg = ExecuteMsg::UpdatePlatformFee {
+        fee: PlatformFee {
+            collector: Addr::unchecked("platform"),
+            bps: 0,
+            flat_fees: vec![],
+            cw20_flat_fees: vec![],
+        },
+    };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg.clone()).unwrap_err();
+    // The flat fee cannot exceed any phase price in its denom
+    let too_high = ExecuteMsg::UpdatePlatformFee {
+        fee: PlatformFee {
+            collector: Addr::unchecked("platform"),
+            bps: 0,
+            flat_fees: coins(101, "uusd"),
+            cw20_flat_fees: vec![],
+        },
+    };
+    let info = mock_info("platform_admin", &[]);
+    execute(deps.as_mut(), mock_env(), info, too_high).unwrap_err();
+    let info = mock_info("platform_admin", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg).unwrap();
+    let value: PlatformFeeResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::PlatformFee {}).unwrap();
+    assert_eq!(value.fee.unwrap().bps, 0);
+}
+
+#[test]
+fn platform_admin_sets_fee_later() {
+    let mut deps = mock_dependencies();
+
+    let now = mock_env().block.time.seconds();
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        mint_start_time: now + 1000,
+        platform_admin: Some(String::from("platform_admin")),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    // A flat fee in another denom does not count against the uusd prices
+    let fee = PlatformFee {
+        collector: Addr::unchecked("platform"),
+        bps: 0,
+        flat_fees: vec![coin(5, "uusd"), coin(1000, "ufury")],
+        cw20_flat_fees: vec![],
+    };
+    let update_msg = ExecuteMsg::UpdatePlatformFee { fee: fee.clone() };
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg.clone()).unwrap_err();
+    let info = mock_info("platform_admin", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg.clone()).unwrap();
+
+    // Phases cannot drop below the flat fee afterwards
+    let phases_msg = ExecuteMsg::UpdatePhases {
+        phases: vec![MintPhaseConfig {
+            name: String::from("cheap"),
+            start_time: now + 2000,
+            end_time: None,
+            prices: coins(4, "uusd"),
+            cw20_prices: vec![],
+            price_mode: PriceMode::Fixed,
+            mint_max: None,
+            supply_max: None,
+            allowlist: None,
+        }],
+    };
+    let info = mock_info("owner", &[]);
+    let err = execute(deps.as_mut(), mock_env(), info, phases_msg).unwrap_err();
+    assert!(err.to_string().contains("Flat platform fee"));
+
+    // The admin can hand the fee over
+    let admin_msg = ExecuteMsg::UpdatePlatformAdmin { admin: String::from("new_admin") };
+    let info = mock_info("platform_admin", &[]);
+    execute(deps.as_mut(), mock_env(), info, admin_msg).unwrap();
+    let info = mock_info("platform_admin", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg.clone()).unwrap_err();
+    let info = mock_info("new_admin", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg).unwrap();
+
+    let value: PlatformFeeResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::PlatformFee {}).unwrap();
+    assert_eq!(value.admin, Some(Addr::unchecked("new_admin")));
+    assert_eq!(value.fee, Some(fee));
+}
+
+#[test]
+fn platform_fee_needs_an_admin() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        platform_fee: Some(PlatformFee {
+            collector: Addr::unchecked("platform"),
+            bps: 100,
+            flat_fees: vec![],
+            cw20_flat_fees: vec![],
+        }),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+}
+
+#[test]
+fn royalties_default_to_the_collection() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        mint_start_time: mock_env().block.time.seconds(),
+        royalty_percentage: 101,
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg.clone()).unwrap_err();
+    let msg = InstantiateMsg {
+        royalty_percentage: 10,
+        royalty_payment_address: String::from("Royalty_Address"),
+        ..msg
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg.clone()).unwrap_err();
+    let msg = InstantiateMsg {
+        royalty_payment_address: String::from("royalty_address"),
+        ..msg
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let mint_msg = |token_id: &str, extension: Option<Metadata>| ExecuteMsg::Mint {
+        extension,
+        token_id: String::from(token_id),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg("1", None)).unwrap();
+
+    // Buyers cannot pick their own royalties
+    let artist = Metadata {
+        royalty_payment_address: Some(String::from("artist")),
+        royalty_percentage: Some(5),
+        ..Metadata::default()
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg("2", Some(artist.clone()))).unwrap_err();
+
+    // The minter can, when fulfilling a request
+    let request_msg = ExecuteMsg::RequestMint {
+        addr: Addr::unchecked("buyer"),
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, request_msg).unwrap();
+    let fulfill_msg = |extension: Metadata| ExecuteMsg::FulfillRequests {
+        tokens: vec![FulfillToken {
+            token_id: String::from("2"),
+            token_uri: None,
+            extension: Some(extension),
+        }],
+    };
+    let too_high = Metadata {
+        royalty_percentage: Some(150),
+        ..Metadata::default()
+    };
+    let info = mock_info("minter", &[]);
+    execute(deps.as_mut(), mock_env(), info, fulfill_msg(too_high)).unwrap_err();
+    let info = mock_info("minter", &[]);
+    execute(deps.as_mut(), mock_env(), info, fulfill_msg(artist)).unwrap();
+
+    let royalty_info = |token_id: &str| QueryMsg::RoyaltyInfo {
+        token_id: String::from(token_id),
+        sale_price: Uint128::from(1000u128),
+    };
+    let value: RoyaltiesInfoResponse = query(deps.as_ref(), mock_env(), royalty_info("1")).unwrap();
+    assert_eq!(value.address, "royalty_address");
+    assert_eq!(value.royalty_amount, Uint128::from(100u128));
+    let value: RoyaltiesInfoResponse = query(deps.as_ref(), mock_env(), royalty_info("2")).unwrap();
+    assert_eq!(value.address, "artist");
+    assert_eq!(value.royalty_amount, Uint128::from(50u128));
+
+    let value: CheckRoyaltiesResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::CheckRoyalties {}).unwrap();
+    assert!(value.royalty_payments);
+}
+
+#[test]
+fn migrate_upgrades_legacy_state() {
+    let mut deps = mock_dependencies();
+
+    // Lay the state out the way 1.0 left it
+    let legacy = LegacyConfig {
+        minter: String::from("minter"),
+        nft_addr: Addr::unchecked("nft_address"),
+        nft_base_uri: String::from("ipfs://base/"),
+        nft_max_supply: Uint128::from(100u128),
+        nft_price_amount: Uint128::from(100u128),
+        owner: String::from("owner"),
+        is_mintable: true,
+        mint_max: Uint128::from(10u128),
+        mint_start_time: 0,
+        nft_symbol: String::from("FNFT"),
+        price_denom: String::from("uusd"),
+        royalty_payment_address: String::from("royalty_address"),
+        royalty_percentage: 10,
+        whitelist_mint_max: Uint128::from(1u128),
+        whitelist_mint_period: 0,
+        whitelist_mint_price_amount: Uint128::from(50u128),
+        paused: false,
+    };
+    deps.storage.set(b"config", &to_json_vec(&legacy).unwrap());
+    state(&mut deps.storage).save(&legacy).unwrap();
+    let mut early = Whitelist::new();
+    for n in 0..=MIGRATE_WHITELIST_BATCH {
+        early.add_address(Addr::unchecked(format!("early{}", n)));
+    }
+    whitelist(&mut deps.storage).save(&early).unwrap();
+    set_contract_version(&mut deps.storage, "furya_nft_minter", "1.0").unwrap();
+
+    // Another contract at the same version is refused
+    let mut other = mock_dependencies();
+    set_contract_version(&mut other.storage, "crates.io:cw721-base", "1.0").unwrap();
+    migrate(other.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+
+    // The whitelist is moved over in a bounded batch
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert!(res.attributes.iter().any(|attr| attr.key == "whitelist_remaining" && attr.value == "1"));
+    assert!(state(&mut deps.storage).may_load().unwrap().is_none());
+    for n in 0..=MIGRATE_WHITELIST_BATCH {
+        let whitelisted: IsWhitelistedResponse = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::IsWhitelisted { addr: format!("early{}", n) },
+        )
+        .unwrap();
+        assert!(whitelisted.is_whitelisted);
+    }
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::MigrateWhitelist { limit: None }).unwrap();
+    assert!(whitelist(&mut deps.storage).may_load().unwrap().is_none());
+
+    // New config fields get their defaults and the schedule is built from the old one
+    let config: ConfigResponse = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    assert_eq!(config.owner, "owner");
+    assert_eq!(config.max_per_tx, 10);
+    assert_eq!(config.request_expiry, 7 * 24 * 60 * 60);
+    let status: MintStatusResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::MintStatus {}).unwrap();
+    assert_eq!(status.phase, MintPhase::Active { id: 0 });
+    assert_eq!(status.prices, coins(100, "uusd"));
+    let mint_msg = ExecuteMsg::Mint {
+        extension: None,
+        token_id: String::from("1"),
+        token_uri: None,
+        proof: None,
+        quantity: None,
+    };
+    let info = mock_info("buyer", &coins(100, "uusd"));
+    execute(deps.as_mut(), mock_env(), info, mint_msg).unwrap();
+
+    let version = get_contract_version(&deps.storage).unwrap();
+    assert_eq!(version.contract, "crates.io:furya-nft-minter");
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+    // Migrating again is a no-op, but never to an older version
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    set_contract_version(&mut deps.storage, version.contract, "99.0.0").unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+}
+
+#[test]
+fn ownership_transfer_takes_two_steps() {
+    let mut deps = mock_dependencies();
+
+    let msg = InstantiateMsg {
+        whitelist_mint_period: 0,
+        mint_start_time: mock_env().block.time.seconds(),
+        ..base_instantiate_msg()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    reply(deps.as_mut(), mock_env(), nft_instantiated("nft_address")).unwrap();
+
+    let now = mock_env().block.time.seconds();
+    let propose = |new_owner: &str| ExecuteMsg::ProposeOwner {
+        new_owner: String::from(new_owner),
+        expiry: Some(now + 100),
+    };
+    let info = mock_info("stranger", &[]);
+    execute(deps.as_mut(), mock_env(), info, propose("stranger")).unwrap_err();
+
+    // A cancelled proposal can no longer be accepted
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, propose("typo")).unwrap();
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::CancelOwnershipTransfer {}).unwrap();
+    let info = mock_info("typo", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::AcceptOwnership {}).unwrap_err();
+
+    // Nor can an expired one
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, propose("new_owner")).unwrap();
+    let value: PendingOwnerResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::PendingOwner {}).unwrap();
+    assert_eq!(value.pending_owner.unwrap().new_owner, Addr::unchecked("new_owner"));
+    let mut late = mock_env();
+    late.block.time = late.block.time.plus_seconds(100);
+    let info = mock_info("new_owner", &[]);
+    execute(deps.as_mut(), late, info, ExecuteMsg::AcceptOwnership {}).unwrap_err();
+
+    let info = mock_info("new_owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::AcceptOwnership {}).unwrap();
+    let config: ConfigResponse = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    assert_eq!(config.owner, "new_owner");
+    let value: PendingOwnerResponse =
+        query(deps.as_ref(), mock_env(), QueryMsg::PendingOwner {}).unwrap();
+    assert!(value.pending_owner.is_none());
+
+    // Renouncing needs payees, since nobody can set them afterwards
+    let info = mock_info("new_owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::RenounceOwnership {}).unwrap_err();
+    let update_msg = ExecuteMsg::UpdatePayees {
+        payees: vec![Payee { address: Addr::unchecked("artist"), share_bps: 10_000 }],
+    };
+    let info = mock_info("new_owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, update_msg).unwrap();
+
+    // Once renounced, owner-only messages are rejected for everyone
+    let info = mock_info("new_owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::RenounceOwnership {}).unwrap();
+    let info = mock_info("new_owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap_err();
+
+    // But anyone can still pay out the funds to the payees
+    deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(100, "uusd"));
+    let info = mock_info("stranger", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::WithdrawFund {}).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: String::from("artist"),
+            amount: coins(100, "uusd"),
+        })
+    );
 }
 
 // Add other integration tests here...
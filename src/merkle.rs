@@ -0,0 +1,71 @@
+use std::convert::TryInto;
+
+use cosmwasm_std::{StdError, StdResult};
+use sha2::{Digest, Sha256};
+
+/// Leaf for `addr`, bound to its allocation when the allowlist carries one.
+pub fn leaf(addr: &str, allocation: Option<u32>) -> [u8; 32] {
+    let data = match allocation {
+        Some(allocation) => format!("{}:{}", addr, allocation),
+        None => addr.to_string(),
+    };
+    Sha256::digest(data.as_bytes()).into()
+}
+
+/// Checks that `proof` links `leaf` to the hex-encoded `root`. Each pair of nodes is hashed
+/// in ascending byte order, so proofs do not need to say which side a sibling is on.
+pub fn verify(root: &str, leaf: [u8; 32], proof: &[String]) -> StdResult<bool> {
+    let root = decode_hash(root)?;
+    let mut hash = leaf;
+    for sibling in proof {
+        let sibling = decode_hash(sibling)?;
+        let (first, second) = if hash <= sibling {
+            (hash, sibling)
+        } else {
+            (sibling, hash)
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(first);
+        hasher.update(second);
+        hash = hasher.finalize().into();
+    }
+    Ok(hash == root)
+}
+
+pub fn decode_hash(hash: &str) -> StdResult<[u8; 32]> {
+    hex::decode(hash)
+        .map_err(|_| StdError::generic_err(format!("Invalid hex hash: {}", hash)))?
+        .try_into()
+        .map_err(|_| StdError::generic_err(format!("Hash must be 32 bytes: {}", hash)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        let mut hasher = Sha256::new();
+        hasher.update(first);
+        hasher.update(second);
+        hasher.finalize().into()
+    }
+
+    #[test]
+    fn test_verify() {
+        let alice = leaf("alice", Some(2));
+        let bob = leaf("bob", None);
+        let carol = leaf("carol", None);
+        let root = hex::encode(hash_pair(hash_pair(alice, bob), carol));
+
+        let proof = vec![hex::encode(bob), hex::encode(carol)];
+        assert!(verify(&root, alice, &proof).unwrap());
+
+        // Wrong allocation, wrong address and truncated proofs all fail
+        assert!(!verify(&root, leaf("alice", Some(3)), &proof).unwrap());
+        assert!(!verify(&root, leaf("mallory", None), &proof).unwrap());
+        assert!(!verify(&root, alice, &proof[..1]).unwrap());
+
+        assert!(verify(&root, alice, &[String::from("zz")]).is_err());
+    }
+}
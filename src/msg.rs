@@ -1,29 +1,53 @@
-use cosmwasm_std::{
-    Addr, CosmosMsg, Deps, DepsMut, Env, Extern, MessageInfo, Response, StdError, StdResult, Storage,
-    Querier, InitResponse,
-};
-use cosmwasm_schema::{to_binary, from_binary, Binary};
+use cosmwasm_schema::QueryResponses;
+use cosmwasm_std::{Addr, Binary, Coin, Uint128};
 use serde::{Deserialize, Serialize};
 use schemars::JsonSchema;
-use cw_storage_plus::{Item, Map};
+use cw20::{Cw20Coin, Cw20ReceiveMsg};
+use crate::state::{
+    MintPhase, MintPhaseConfig, Payee, Payment, PendingOwner, PlatformFee, TokenRequest, VoucherSigner,
+    WhitelistEntry,
+};
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct InstantiateMsg {
-    pub mint_max: Option<Uint128>,
+    pub minter: String,
+    pub owner: String,
+    pub is_mintable: bool,
+    pub mint_max: Uint128,
+    pub mint_start_time: u64,
     pub nft_base_uri: String,
+    /// Code id of the cw721 collection instantiated alongside this contract.
     pub nft_ci: u64,
     pub nft_max_supply: Uint128,
     pub nft_name: String,
     pub nft_price_amount: Uint128,
     pub nft_symbol: String,
     pub price_denom: String,
-    pub royalty_payment_address: Option<String>,
-    pub royalty_percentage: Option<u32>,
-    pub whitelist_mint_max: Option<Uint128>,
+    pub royalty_payment_address: String,
+    pub royalty_percentage: u32,
+    pub whitelist_mint_max: Uint128,
     pub whitelist_mint_period: u64,
-    pub whitelist_mint_price_amount: Option<Uint128>,
+    pub whitelist_mint_price_amount: Uint128,
+    /// Seconds after which a pending token request may be cancelled and refunded.
+    pub request_expiry: Option<u64>,
+    /// Mint schedule. Defaults to a whitelist phase followed by a public phase.
+    pub phases: Option<Vec<MintPhaseConfig>>,
+    /// Key allowed to sign mint vouchers. Voucher mints are disabled without one.
+    pub voucher_signer: Option<VoucherSigner>,
+    /// Most tokens a single `Mint` or `RequestMint` may ask for. Defaults to 10.
+    pub max_per_tx: Option<u32>,
+    /// Tokens held back from `nft_max_supply` for owner airdrops. Defaults to none.
+    pub reserved_supply: Option<Uint128>,
+    /// Launchpad fee taken out of every paid mint. Needs `platform_admin`.
+    pub platform_fee: Option<PlatformFee>,
+    /// Only address allowed to set or change the platform fee later. Without one the fee
+    /// can never change.
+    pub platform_admin: Option<String>,
 }
 
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
 pub enum ExecuteMsg {
     UpdateConfig {
@@ -32,106 +56,433 @@ pub enum ExecuteMsg {
         nft_base_uri: Option<String>,
         nft_max_supply: Option<Uint128>,
         nft_price_amount: Option<Uint128>,
-        owner: Option<String>,
+        max_per_tx: Option<u32>,
+    },
+    /// Starts handing the contract over to `new_owner`, who has to accept before `expiry`.
+    /// Replaces any earlier proposal. Owner only.
+    ProposeOwner {
+        new_owner: String,
+        expiry: Option<u64>,
     },
+    /// Completes a pending transfer. Proposed owner only.
+    AcceptOwnership {},
+    /// Withdraws a pending transfer. Owner only.
+    CancelOwnershipTransfer {},
+    /// Gives up ownership for good, leaving every owner-only message unusable. Owner only, and
+    /// only once payees are set.
+    RenounceOwnership {},
     Whitelist {
         addrs: Vec<Addr>,
     },
+    RemoveFromWhitelist {
+        addrs: Vec<Addr>,
+    },
+    ClearWhitelist,
+    /// Adds or updates whitelist entries with their own allocation and optional price.
+    SetWhitelistAllocations {
+        allocations: Vec<WhitelistAllocation>,
+    },
+    /// Moves up to `limit` addresses from the legacy single-item whitelist into the keyed layout.
+    MigrateWhitelist {
+        limit: Option<u32>,
+    },
     StartMint,
+    /// Replaces the mint schedule. Phases that have already started must be kept as they are.
+    UpdatePhases {
+        phases: Vec<MintPhaseConfig>,
+    },
+    /// Queues `quantity` (default 1) token requests for `addr`, one record per token.
     RequestMint {
         addr: Addr,
+        proof: Option<MerkleProof>,
+        quantity: Option<u32>,
     },
+    /// Pays for a `ReceiveMsg` mint with cw20 tokens.
+    Receive(Cw20ReceiveMsg),
+    /// Mints `quantity` (default 1) tokens to the sender. Batches are numbered
+    /// `{token_id}-1` to `{token_id}-{quantity}`.
     Mint {
         extension: Option<Metadata>,
         token_id: String,
         token_uri: Option<String>,
+        proof: Option<MerkleProof>,
+        quantity: Option<u32>,
+    },
+    /// Mints to `voucher.recipient` on the strength of a voucher signed by the configured
+    /// `voucher_signer`, bypassing the phase schedule and allowlists.
+    MintWithVoucher {
+        voucher: MintVoucher,
+        signature: Binary,
+        extension: Option<Metadata>,
+        token_id: String,
+        token_uri: Option<String>,
+    },
+    /// Refunds what the sender paid above the clearing price of a settled rebate auction and
+    /// settles the platform fee on the rest.
+    ClaimAuctionRebate {
+        phase_id: u32,
+    },
+    /// Sets or removes the key that signs mint vouchers.
+    UpdateVoucherSigner {
+        signer: Option<VoucherSigner>,
+    },
+    /// Rotates the merkle root of a phase that uses a merkle allowlist.
+    UpdateMerkleRoot {
+        phase_id: u32,
+        root: String,
+    },
+    /// Mints the oldest pending token requests, one per entry in `tokens`. Minter only.
+    FulfillRequests {
+        tokens: Vec<FulfillToken>,
+    },
+    /// Refunds a pending token request once it has expired or minting is closed.
+    CancelRequest {
+        index: Uint128,
+    },
+    /// Mints free tokens out of the reserved supply, ignoring phases and pauses. Owner only.
+    /// Tokens are numbered `reserved-1` upwards.
+    Airdrop {
+        recipients: Vec<(String, u32)>,
     },
     Pause,
+    /// Permanently stops minting. Pending requests become refundable.
+    CloseMint,
     Unpause,
+    /// Pays everything not held for pending requests or rebates out to the payees. Owner only,
+    /// or anyone once ownership has been renounced.
     WithdrawFund,
+    /// Replaces the withdrawal payees. Owner only.
+    UpdatePayees {
+        payees: Vec<Payee>,
+    },
+    /// Sets or replaces the platform fee. Platform admin only.
+    UpdatePlatformFee {
+        fee: PlatformFee,
+    },
+    /// Hands the platform fee over to another admin. Platform admin only.
+    UpdatePlatformAdmin {
+        admin: String,
+    },
 }
 
+/// Hook messages for `ExecuteMsg::Receive`, paid for with the attached cw20 tokens. They behave
+/// like the `ExecuteMsg` variants of the same name, with the cw20 sender as the buyer.
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
-pub struct Metadata {
-    pub animation_url: Option<String>,
-    pub attributes: Option<Vec<Attribute>>,
-    pub description: Option<String>,
-    pub external_url: Option<String>,
-    pub image: Option<String>,
-    pub name: Option<String>,
-    pub royalty_payment_address: Option<String>,
-    pub royalty_percentage: Option<u32>,
+pub enum ReceiveMsg {
+    RequestMint {
+        addr: Addr,
+        proof: Option<MerkleProof>,
+        quantity: Option<u32>,
+    },
+    Mint {
+        extension: Option<Metadata>,
+        token_id: String,
+        token_uri: Option<String>,
+        proof: Option<MerkleProof>,
+        quantity: Option<u32>,
+    },
 }
 
+/// Off-chain mint authorization. The signer signs the sha256 digest of the contract address
+/// followed by the JSON encoding of the voucher.
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
-pub struct Attribute {
-    pub trait_type: String,
-    pub value: String,
+pub struct MintVoucher {
+    pub recipient: String,
+    /// Price of each token minted with this voucher.
+    pub price: Coin,
+    /// Tokens that may be minted with this voucher across all transactions.
+    pub max_quantity: u32,
+    /// Block time after which the voucher is no longer accepted.
+    pub expiry: u64,
+    pub nonce: u64,
+}
+
+/// Proof that the minting address is in a phase's merkle allowlist.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct MerkleProof {
+    /// Hex-encoded sibling hashes from the leaf up to the root.
+    pub proof: Vec<String>,
+    /// Allocation committed to in the leaf, if the allowlist carries one.
+    pub allocation: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WhitelistAllocation {
+    pub addr: String,
+    pub mint_allocation: u32,
+    pub price: Option<Uint128>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct FulfillToken {
+    pub token_id: String,
+    pub token_uri: Option<String>,
+    pub extension: Option<Metadata>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema, QueryResponses)]
+pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(IsWhitelistedResponse)]
+    IsWhitelisted { addr: String },
+    #[returns(WhitelistSizeResponse)]
+    WhitelistSize {},
+    #[returns(WhitelistEntriesResponse)]
+    WhitelistEntries {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(TokenRequestsCountResponse)]
+    TokenRequestsCount {},
+    #[returns(CurrentSupplyResponse)]
+    CurrentSupply {},
+    /// Current mint phase, when the next one starts and the price to pay now.
+    #[returns(MintStatusResponse)]
+    MintStatus {},
+    #[returns(PhasesResponse)]
+    Phases {},
+    /// Total cost of minting `quantity` tokens in the current phase right now, before any
+    /// allowlist price overrides.
+    #[returns(PriceQuoteResponse)]
+    PriceQuote {
+        quantity: u32,
+    },
+    /// Ownership transfer waiting to be accepted, if any.
+    #[returns(PendingOwnerResponse)]
+    PendingOwner {},
+    /// Who withdrawals are split between.
+    #[returns(PayeesResponse)]
+    Payees {},
+    /// The platform fee, its admin and what it has collected so far.
+    #[returns(PlatformFeeResponse)]
+    PlatformFee {},
+    /// Revenue from minted tokens after platform fees, per denom.
+    #[returns(RevenueResponse)]
+    Revenue {},
+    /// Tokens already minted with the voucher carrying `nonce`.
+    #[returns(VoucherUsageResponse)]
+    VoucherUsage {
+        nonce: u64,
+    },
+    /// Checks a merkle proof for `addr` against a phase's current root.
+    #[returns(VerifyMerkleProofResponse)]
+    VerifyMerkleProof {
+        phase_id: u32,
+        addr: String,
+        proof: MerkleProof,
+    },
+    /// How many more tokens `addr` may mint in the current phase. Merkle phases need the
+    /// address's proof to resolve its allocation.
+    #[returns(MintAllocationResponse)]
+    MintAllocation {
+        addr: String,
+        proof: Option<MerkleProof>,
+    },
+    #[returns(TokenRequestByIndexResponse)]
+    TokenRequestByIndex { index: Uint128 },
+    /// CW2981: who is owed royalties on a sale of `token_id` at `sale_price`, and how much.
+    #[returns(RoyaltiesInfoResponse)]
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128,
+    },
+    /// CW2981: whether this collection pays royalties.
+    #[returns(CheckRoyaltiesResponse)]
+    CheckRoyalties {},
 }
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
-pub struct Config {
-    pub minter: Addr,
+pub struct ConfigResponse {
+    pub minter: String,
     pub nft_addr: Addr,
     pub nft_base_uri: String,
     pub nft_max_supply: Uint128,
     pub nft_price_amount: Uint128,
-    pub owner: Addr,
+    pub owner: String,
     pub is_mintable: bool,
-    pub mint_max: u32,
+    pub mint_max: Uint128,
     pub mint_start_time: u64,
     pub nft_symbol: String,
     pub price_denom: String,
     pub royalty_payment_address: String,
-    pub royalty_percentage: Uint128,
-    pub whitelist_mint_max: u32,
+    pub royalty_percentage: u32,
+    pub whitelist_mint_max: Uint128,
     pub whitelist_mint_period: u64,
     pub whitelist_mint_price_amount: Uint128,
     pub paused: bool,
+    pub request_expiry: u64,
+    pub closed: bool,
+    pub voucher_signer: Option<VoucherSigner>,
+    pub max_per_tx: u32,
+    pub reserved_supply: Uint128,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct IsWhitelistedResponse {
+    pub is_whitelisted: bool,
+    /// Tokens the address may still mint, or `None` if it is not capped.
+    pub remaining_allocation: Option<Uint128>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WhitelistSizeResponse {
+    pub whitelist_size: u64,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WhitelistEntryResponse {
+    pub addr: String,
+    pub entry: WhitelistEntry,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WhitelistEntriesResponse {
+    pub entries: Vec<WhitelistEntryResponse>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct TokenRequestsCountResponse {
+    pub token_requests_count: u64,
+    pub pending_count: u64,
+    pub refunded_count: u64,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct CurrentSupplyResponse {
+    /// Tokens minted so far.
+    pub current_supply: Uint128,
+    /// Tokens held back for pending token requests.
+    pub pending_supply: Uint128,
+    /// Tokens left in the airdrop reserve.
+    pub airdrop_remaining: Uint128,
+    pub max_supply: Uint128,
+    pub sold_out: bool,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct MintStatusResponse {
+    pub phase: MintPhase,
+    pub phase_name: Option<String>,
+    /// Seconds until the next phase starts, if that is scheduled.
+    pub next_phase_in: Option<u64>,
+    /// Accepted prices of one token, if the current phase allows minting.
+    pub prices: Vec<Coin>,
+    pub cw20_prices: Vec<Cw20Coin>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PriceQuoteResponse {
+    pub phase: MintPhase,
+    /// One total per accepted payment option. Empty unless a phase is open.
+    pub prices: Vec<Coin>,
+    pub cw20_prices: Vec<Cw20Coin>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PendingOwnerResponse {
+    pub pending_owner: Option<PendingOwner>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PayeesResponse {
+    pub payees: Vec<Payee>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct RoyaltiesInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct CheckRoyaltiesResponse {
+    pub royalty_payments: bool,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PlatformFeeResponse {
+    pub admin: Option<Addr>,
+    pub fee: Option<PlatformFee>,
+    /// Charged so far, per denom or cw20 token.
+    pub collected: Vec<Payment>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct RevenueResponse {
+    /// Paid for minted tokens so far, per denom or cw20 token.
+    pub revenue: Vec<Payment>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct MintAllocationResponse {
+    pub phase: MintPhase,
+    pub minted: u32,
+    /// `None` when the phase has no per-address cap.
+    pub mint_max: Option<Uint128>,
+    pub remaining: Option<Uint128>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct VoucherUsageResponse {
+    pub used: u32,
 }
 
-pub const CONFIG_KEY: &[u8] = b"config";
-pub const STATE_KEY: &[u8] = b"state";
-pub const WHITELIST_KEY: &[u8] = b"whitelist";
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct VerifyMerkleProofResponse {
+    pub valid: bool,
+}
 
-pub fn config(storage: &mut dyn Storage) -> Item<Config> {
-    Item::new(storage, CONFIG_KEY)
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PhasesResponse {
+    pub phases: Vec<MintPhaseConfig>,
 }
 
-pub fn state(storage: &mut dyn Storage) -> Item<Config> {
-    Item::new(storage, STATE_KEY)
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct TokenRequestByIndexResponse {
+    pub index: Uint128,
+    pub token_request: TokenRequest,
+    /// Whether a pending request is past its expiry and can be cancelled.
+    pub expired: bool,
 }
 
-pub fn whitelist(storage: &mut dyn Storage) -> Map<Whitelist> {
-    Map::new(storage, WHITELIST_KEY)
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct Metadata {
+    pub animation_url: Option<String>,
+    pub attributes: Option<Vec<Attribute>>,
+    pub description: Option<String>,
+    pub external_url: Option<String>,
+    pub image: Option<String>,
+    pub name: Option<String>,
+    /// Overrides the collection's royalty recipient for this token.
+    pub royalty_payment_address: Option<String>,
+    /// Overrides the collection's royalty percentage for this token.
+    pub royalty_percentage: Option<u32>,
 }
 
-pub fn init(
-    deps: &mut Extern<DefaultApi, Storage, Querier>,
-    env: Env,
-    msg: InstantiateMsg,
-) -> StdResult<InitResponse> {
-    let config = Config {
-        minter: deps.api.addr_validate(&msg.minter)?,
-        nft_addr: msg.nft_addr,
-        nft_base_uri: msg.nft_base_uri,
-        nft_max_supply: msg.nft_max_supply,
-        nft_price_amount: msg.nft_price_amount,
-        owner: deps.api.addr_validate(&msg.owner)?,
-        is_mintable: msg.is_mintable,
-        mint_max: msg.mint_max,
-        mint_start_time: msg.mint_start_time,
-        nft_symbol: msg.nft_symbol,
-        price_denom: msg.price_denom,
-        royalty_payment_address: msg.royalty_payment_address,
-        royalty_percentage: msg.royalty_percentage,
-        whitelist_mint_max: msg.whitelist_mint_max,
-        whitelist_mint_period: msg.whitelist_mint_period,
-        whitelist_mint_price_amount: msg.whitelist_mint_price_amount,
-        paused: false,
-    };
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct Attribute {
+    pub trait_type: String,
+    pub value: String,
+}
 
-    config(deps.storage()).save(&config)?;
+/// Instantiates the cw721 collection for `InstantiateMsg.nft_ci`.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct Cw721InstantiateMsg {
+    pub name: String,
+    pub symbol: String,
+    pub minter: String,
+}
 
-    Ok(InitResponse::default())
+/// Execute message understood by the cw721 collection at `Config.nft_addr`.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721ExecuteMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: Option<Metadata>,
+    },
 }
+
@@ -1,32 +1,325 @@
-use cosmwasm_std::{Addr, StdResult, Storage, ReadonlyStorage};
+// cosmwasm-storage is deprecated in favour of cw-storage-plus; the layout below predates that.
+#![allow(deprecated)]
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, StdError, StdResult, Storage};
+use cw20::{Cw20Coin, Cw20CoinVerified};
+use schemars::JsonSchema;
 use serde::{Serialize, Deserialize};
 use cosmwasm_std::Uint128;
-use cosmwasm_storage::{singleton, singleton_read, ReadonlySingleton, Singleton};
+use cosmwasm_storage::{
+    bucket, bucket_read, singleton, singleton_read, Bucket, ReadonlyBucket, ReadonlySingleton,
+    Singleton,
+};
 
 const CONFIG_KEY: &[u8] = b"config";
 const STATE_KEY: &[u8] = b"state";
 const WHITELIST_KEY: &[u8] = b"whitelist";
+const SUPPLY_KEY: &[u8] = b"supply";
+const AIRDROPPED_KEY: &[u8] = b"airdropped";
+const TOKEN_REQUESTS_KEY: &[u8] = b"token_requests";
+const TOKEN_REQUESTS_COUNT_KEY: &[u8] = b"token_requests_count";
+const TOKEN_REQUESTS_HEAD_KEY: &[u8] = b"token_requests_head";
+const PENDING_TOKEN_REQUESTS_KEY: &[u8] = b"pending_token_requests";
+const REFUNDED_TOKEN_REQUESTS_KEY: &[u8] = b"refunded_token_requests";
+const MINT_COUNTS_KEY: &[u8] = b"mint_counts";
+const PHASES_KEY: &[u8] = b"phases";
+const PHASE_SUPPLY_KEY: &[u8] = b"phase_supply";
+const WHITELIST_ENTRIES_KEY: &[u8] = b"whitelist_entries";
+const WHITELIST_SIZE_KEY: &[u8] = b"whitelist_size";
+const WHITELIST_GENERATION_KEY: &[u8] = b"whitelist_generation";
+const VOUCHER_NONCES_KEY: &[u8] = b"voucher_nonces";
+const ESCROW_KEY: &[u8] = b"escrow";
+const CW20_ESCROW_KEY: &[u8] = b"cw20_escrow";
+const REVENUE_KEY: &[u8] = b"revenue";
+const PAYEES_KEY: &[u8] = b"payees";
+const PENDING_OWNER_KEY: &[u8] = b"pending_owner";
+const TOKEN_ROYALTIES_KEY: &[u8] = b"token_royalties";
+const PLATFORM_FEE_KEY: &[u8] = b"platform_fee";
+const PLATFORM_ADMIN_KEY: &[u8] = b"platform_admin";
+const PLATFORM_FEES_KEY: &[u8] = b"platform_fees";
+const AUCTION_BIDS_KEY: &[u8] = b"auction_bids";
+const AUCTION_CLEARING_PRICE_KEY: &[u8] = b"auction_clearing_price";
 
-pub fn config<S: Storage>(storage: &S) -> Singleton<S, Config> {
+pub fn config(storage: &mut dyn Storage) -> Singleton<'_, Config> {
     singleton(storage, CONFIG_KEY)
 }
 
-pub fn read_config<S: ReadonlyStorage>(storage: &S) -> ReadonlySingleton<S, Config> {
+pub fn read_config(storage: &dyn Storage) -> ReadonlySingleton<'_, Config> {
     singleton_read(storage, CONFIG_KEY)
 }
 
-pub fn state<S: Storage>(storage: &S) -> Singleton<S, Config> {
+/// `config` as 1.0 laid it out. Only read when migrating.
+pub fn read_legacy_config(storage: &dyn Storage) -> ReadonlySingleton<'_, LegacyConfig> {
+    singleton_read(storage, CONFIG_KEY)
+}
+
+/// Legacy copy of `config` written by 1.0. Only read when migrating.
+pub fn state(storage: &mut dyn Storage) -> Singleton<'_, LegacyConfig> {
     singleton(storage, STATE_KEY)
 }
 
-pub fn read_state<S: ReadonlyStorage>(storage: &S) -> ReadonlySingleton<S, Config> {
+pub fn read_state(storage: &dyn Storage) -> ReadonlySingleton<'_, LegacyConfig> {
     singleton_read(storage, STATE_KEY)
 }
 
-pub fn whitelist<S: Storage>(storage: &S) -> Singleton<S, Whitelist> {
+/// Legacy single-item whitelist layout. Only read until it has been migrated to
+/// `whitelist_entries`.
+pub fn whitelist(storage: &mut dyn Storage) -> Singleton<'_, Whitelist> {
     singleton(storage, WHITELIST_KEY)
 }
 
+pub fn read_whitelist(storage: &dyn Storage) -> ReadonlySingleton<'_, Whitelist> {
+    singleton_read(storage, WHITELIST_KEY)
+}
+
+/// Whitelisted addresses keyed by address. Entries from older generations are ignored, so
+/// bumping the generation clears the whitelist without touching every entry.
+pub fn whitelist_entries(storage: &mut dyn Storage, generation: u32) -> Bucket<'_, WhitelistEntry> {
+    Bucket::multilevel(storage, &[WHITELIST_ENTRIES_KEY, &generation.to_be_bytes()])
+}
+
+pub fn read_whitelist_entries(storage: &dyn Storage, generation: u32) -> ReadonlyBucket<'_, WhitelistEntry> {
+    ReadonlyBucket::multilevel(storage, &[WHITELIST_ENTRIES_KEY, &generation.to_be_bytes()])
+}
+
+pub fn whitelist_generation(storage: &mut dyn Storage) -> Singleton<'_, u32> {
+    singleton(storage, WHITELIST_GENERATION_KEY)
+}
+
+pub fn read_whitelist_generation(storage: &dyn Storage) -> ReadonlySingleton<'_, u32> {
+    singleton_read(storage, WHITELIST_GENERATION_KEY)
+}
+
+pub fn whitelist_size(storage: &mut dyn Storage) -> Singleton<'_, u64> {
+    singleton(storage, WHITELIST_SIZE_KEY)
+}
+
+pub fn read_whitelist_size(storage: &dyn Storage) -> ReadonlySingleton<'_, u64> {
+    singleton_read(storage, WHITELIST_SIZE_KEY)
+}
+
+pub fn supply(storage: &mut dyn Storage) -> Singleton<'_, Uint128> {
+    singleton(storage, SUPPLY_KEY)
+}
+
+pub fn read_supply(storage: &dyn Storage) -> ReadonlySingleton<'_, Uint128> {
+    singleton_read(storage, SUPPLY_KEY)
+}
+
+/// Tokens airdropped out of the reserved supply. Also counted in `supply`.
+pub fn airdropped(storage: &mut dyn Storage) -> Singleton<'_, Uint128> {
+    singleton(storage, AIRDROPPED_KEY)
+}
+
+pub fn read_airdropped(storage: &dyn Storage) -> ReadonlySingleton<'_, Uint128> {
+    singleton_read(storage, AIRDROPPED_KEY)
+}
+
+/// Token requests keyed by their big-endian `u64` queue index.
+pub fn token_requests(storage: &mut dyn Storage) -> Bucket<'_, TokenRequest> {
+    bucket(storage, TOKEN_REQUESTS_KEY)
+}
+
+pub fn read_token_requests(storage: &dyn Storage) -> ReadonlyBucket<'_, TokenRequest> {
+    bucket_read(storage, TOKEN_REQUESTS_KEY)
+}
+
+pub fn token_requests_count(storage: &mut dyn Storage) -> Singleton<'_, u64> {
+    singleton(storage, TOKEN_REQUESTS_COUNT_KEY)
+}
+
+pub fn read_token_requests_count(storage: &dyn Storage) -> ReadonlySingleton<'_, u64> {
+    singleton_read(storage, TOKEN_REQUESTS_COUNT_KEY)
+}
+
+/// Index of the oldest token request that may still be pending.
+pub fn token_requests_head(storage: &mut dyn Storage) -> Singleton<'_, u64> {
+    singleton(storage, TOKEN_REQUESTS_HEAD_KEY)
+}
+
+pub fn read_token_requests_head(storage: &dyn Storage) -> ReadonlySingleton<'_, u64> {
+    singleton_read(storage, TOKEN_REQUESTS_HEAD_KEY)
+}
+
+pub fn pending_token_requests(storage: &mut dyn Storage) -> Singleton<'_, u64> {
+    singleton(storage, PENDING_TOKEN_REQUESTS_KEY)
+}
+
+pub fn read_pending_token_requests(storage: &dyn Storage) -> ReadonlySingleton<'_, u64> {
+    singleton_read(storage, PENDING_TOKEN_REQUESTS_KEY)
+}
+
+pub fn refunded_token_requests(storage: &mut dyn Storage) -> Singleton<'_, u64> {
+    singleton(storage, REFUNDED_TOKEN_REQUESTS_KEY)
+}
+
+pub fn read_refunded_token_requests(storage: &dyn Storage) -> ReadonlySingleton<'_, u64> {
+    singleton_read(storage, REFUNDED_TOKEN_REQUESTS_KEY)
+}
+
+/// Tokens minted or requested per address during phase `phase_id`.
+pub fn mint_counts(storage: &mut dyn Storage, phase_id: u32) -> Bucket<'_, u32> {
+    Bucket::multilevel(storage, &[MINT_COUNTS_KEY, &phase_id.to_be_bytes()])
+}
+
+pub fn read_mint_counts(storage: &dyn Storage, phase_id: u32) -> ReadonlyBucket<'_, u32> {
+    ReadonlyBucket::multilevel(storage, &[MINT_COUNTS_KEY, &phase_id.to_be_bytes()])
+}
+
+/// Native payments held for pending token requests, keyed by denom.
+pub fn escrow(storage: &mut dyn Storage) -> Bucket<'_, Uint128> {
+    bucket(storage, ESCROW_KEY)
+}
+
+pub fn read_escrow(storage: &dyn Storage) -> ReadonlyBucket<'_, Uint128> {
+    bucket_read(storage, ESCROW_KEY)
+}
+
+/// Every cw20 token the contract has been paid in, keyed by token address, with the amount
+/// held for pending token requests.
+pub fn cw20_escrow(storage: &mut dyn Storage) -> Bucket<'_, Uint128> {
+    bucket(storage, CW20_ESCROW_KEY)
+}
+
+pub fn read_cw20_escrow(storage: &dyn Storage) -> ReadonlyBucket<'_, Uint128> {
+    bucket_read(storage, CW20_ESCROW_KEY)
+}
+
+/// Total paid for minted tokens in each denom or cw20 token, keyed by `Payment::key`.
+pub fn revenue(storage: &mut dyn Storage) -> Bucket<'_, Payment> {
+    bucket(storage, REVENUE_KEY)
+}
+
+pub fn read_revenue(storage: &dyn Storage) -> ReadonlyBucket<'_, Payment> {
+    bucket_read(storage, REVENUE_KEY)
+}
+
+/// Royalties of tokens minted with their own, keyed by token id. Other tokens use the
+/// collection default in `Config`.
+pub fn token_royalties(storage: &mut dyn Storage) -> Bucket<'_, Royalty> {
+    bucket(storage, TOKEN_ROYALTIES_KEY)
+}
+
+pub fn read_token_royalties(storage: &dyn Storage) -> ReadonlyBucket<'_, Royalty> {
+    bucket_read(storage, TOKEN_ROYALTIES_KEY)
+}
+
+/// Launchpad fee on paid mints. Kept out of `Config` so only the platform admin can change it.
+pub fn platform_fee(storage: &mut dyn Storage) -> Singleton<'_, PlatformFee> {
+    singleton(storage, PLATFORM_FEE_KEY)
+}
+
+pub fn read_platform_fee(storage: &dyn Storage) -> ReadonlySingleton<'_, PlatformFee> {
+    singleton_read(storage, PLATFORM_FEE_KEY)
+}
+
+/// The only address allowed to set or change the platform fee, with or without one in place.
+pub fn platform_admin(storage: &mut dyn Storage) -> Singleton<'_, Addr> {
+    singleton(storage, PLATFORM_ADMIN_KEY)
+}
+
+pub fn read_platform_admin(storage: &dyn Storage) -> ReadonlySingleton<'_, Addr> {
+    singleton_read(storage, PLATFORM_ADMIN_KEY)
+}
+
+/// Platform fees charged so far in each denom or cw20 token, keyed by `Payment::key`.
+pub fn platform_fees(storage: &mut dyn Storage) -> Bucket<'_, Payment> {
+    bucket(storage, PLATFORM_FEES_KEY)
+}
+
+pub fn read_platform_fees(storage: &dyn Storage) -> ReadonlyBucket<'_, Payment> {
+    bucket_read(storage, PLATFORM_FEES_KEY)
+}
+
+/// Ownership transfer waiting for the proposed owner to accept it.
+pub fn pending_owner(storage: &mut dyn Storage) -> Singleton<'_, PendingOwner> {
+    singleton(storage, PENDING_OWNER_KEY)
+}
+
+pub fn read_pending_owner(storage: &dyn Storage) -> ReadonlySingleton<'_, PendingOwner> {
+    singleton_read(storage, PENDING_OWNER_KEY)
+}
+
+/// Who `WithdrawFund` pays out to. Unset until the owner configures it, paying the owner alone.
+pub fn payees(storage: &mut dyn Storage) -> Singleton<'_, Vec<Payee>> {
+    singleton(storage, PAYEES_KEY)
+}
+
+pub fn read_payees(storage: &dyn Storage) -> ReadonlySingleton<'_, Vec<Payee>> {
+    singleton_read(storage, PAYEES_KEY)
+}
+
+/// Purchases in a rebate auction phase that have not claimed their rebate, keyed by address.
+pub fn auction_bids(storage: &mut dyn Storage, phase_id: u32) -> Bucket<'_, AuctionBid> {
+    Bucket::multilevel(storage, &[AUCTION_BIDS_KEY, &phase_id.to_be_bytes()])
+}
+
+pub fn read_auction_bids(storage: &dyn Storage, phase_id: u32) -> ReadonlyBucket<'_, AuctionBid> {
+    ReadonlyBucket::multilevel(storage, &[AUCTION_BIDS_KEY, &phase_id.to_be_bytes()])
+}
+
+/// Price of the latest sale in each auction phase, keyed by big-endian phase id. Prices only
+/// fall, so once the auction settles this is its clearing price.
+pub fn auction_clearing_price(storage: &mut dyn Storage) -> Bucket<'_, Uint128> {
+    bucket(storage, AUCTION_CLEARING_PRICE_KEY)
+}
+
+pub fn read_auction_clearing_price(storage: &dyn Storage) -> ReadonlyBucket<'_, Uint128> {
+    bucket_read(storage, AUCTION_CLEARING_PRICE_KEY)
+}
+
+/// Tokens minted with each voucher, keyed by big-endian nonce.
+pub fn voucher_nonces(storage: &mut dyn Storage) -> Bucket<'_, u32> {
+    bucket(storage, VOUCHER_NONCES_KEY)
+}
+
+pub fn read_voucher_nonces(storage: &dyn Storage) -> ReadonlyBucket<'_, u32> {
+    bucket_read(storage, VOUCHER_NONCES_KEY)
+}
+
+/// The mint schedule, ordered by start time. A phase's id is its index in this list.
+pub fn phases(storage: &mut dyn Storage) -> Singleton<'_, Vec<MintPhaseConfig>> {
+    singleton(storage, PHASES_KEY)
+}
+
+pub fn read_phases(storage: &dyn Storage) -> ReadonlySingleton<'_, Vec<MintPhaseConfig>> {
+    singleton_read(storage, PHASES_KEY)
+}
+
+/// Tokens minted or requested per phase, keyed by big-endian phase id.
+pub fn phase_supply(storage: &mut dyn Storage) -> Bucket<'_, Uint128> {
+    bucket(storage, PHASE_SUPPLY_KEY)
+}
+
+pub fn read_phase_supply(storage: &dyn Storage) -> ReadonlyBucket<'_, Uint128> {
+    bucket_read(storage, PHASE_SUPPLY_KEY)
+}
+
+/// The config layout written by 1.0, before the fields `migrate` fills in with defaults.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct LegacyConfig {
+    pub minter: String,
+    pub nft_addr: Addr,
+    pub nft_base_uri: String,
+    pub nft_max_supply: Uint128,
+    pub nft_price_amount: Uint128,
+    pub owner: String,
+    pub is_mintable: bool,
+    pub mint_max: Uint128,
+    pub mint_start_time: u64,
+    pub nft_symbol: String,
+    pub price_denom: String,
+    pub royalty_payment_address: String,
+    pub royalty_percentage: u32,
+    pub whitelist_mint_max: Uint128,
+    pub whitelist_mint_period: u64,
+    pub whitelist_mint_price_amount: Uint128,
+    pub paused: bool,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Config {
     pub minter: String,
@@ -46,32 +339,373 @@ pub struct Config {
     pub whitelist_mint_period: u64,
     pub whitelist_mint_price_amount: Uint128,
     pub paused: bool,
+    pub request_expiry: u64,
+    pub closed: bool,
+    pub voucher_signer: Option<VoucherSigner>,
+    pub max_per_tx: u32,
+    /// Part of `nft_max_supply` held back for `ExecuteMsg::Airdrop`.
+    pub reserved_supply: Uint128,
 }
 
 impl Config {
-    pub fn save<S: Storage>(&self, storage: &mut S) -> StdResult<()> {
+    pub fn save(&self, storage: &mut dyn Storage) -> StdResult<()> {
         config(storage).save(self)
     }
 
-    pub fn load<S: ReadonlyStorage>(storage: &S) -> StdResult<Config> {
+    pub fn load(storage: &dyn Storage) -> StdResult<Config> {
         read_config(storage).load()
     }
 
-    pub fn state_save<S: Storage>(&self, storage: &mut S) -> StdResult<()> {
-        state(storage).save(self)
+    /// Whitelist then public phases built from the single-schedule fields.
+    pub fn default_phases(&self) -> Vec<MintPhaseConfig> {
+        let public_start = self.mint_start_time + self.whitelist_mint_period;
+        let mut phases = vec![];
+        if self.whitelist_mint_period > 0 {
+            phases.push(MintPhaseConfig {
+                name: "whitelist".to_string(),
+                start_time: self.mint_start_time,
+                end_time: Some(public_start),
+                prices: vec![Coin {
+                    denom: self.price_denom.clone(),
+                    amount: self.whitelist_mint_price_amount,
+                }],
+                cw20_prices: vec![],
+                price_mode: PriceMode::Fixed,
+                mint_max: Some(self.whitelist_mint_max),
+                supply_max: None,
+                allowlist: Some(Allowlist::Whitelist),
+            });
+        }
+        phases.push(MintPhaseConfig {
+            name: "public".to_string(),
+            start_time: public_start,
+            end_time: None,
+            prices: vec![Coin {
+                denom: self.price_denom.clone(),
+                amount: self.nft_price_amount,
+            }],
+            cw20_prices: vec![],
+            price_mode: PriceMode::Fixed,
+            mint_max: Some(self.mint_max),
+            supply_max: None,
+            allowlist: None,
+        });
+        phases
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct MintPhaseConfig {
+    pub name: String,
+    pub start_time: u64,
+    /// `None` keeps the phase open until the mint sells out or is closed.
+    pub end_time: Option<u64>,
+    /// Accepted native prices per token. The buyer picks one by attaching its denom.
+    pub prices: Vec<Coin>,
+    /// Accepted prices per token when paying with a cw20 token through `ExecuteMsg::Receive`.
+    pub cw20_prices: Vec<Cw20Coin>,
+    #[serde(default)]
+    pub price_mode: PriceMode,
+    /// Per-address cap for the phase.
+    pub mint_max: Option<Uint128>,
+    /// Tokens the phase may sell in total.
+    pub supply_max: Option<Uint128>,
+    /// Restricts the phase to the addresses on this list.
+    pub allowlist: Option<Allowlist>,
+}
+
+impl MintPhaseConfig {
+    pub fn is_active_at(&self, time: u64) -> bool {
+        self.start_time <= time && self.end_time.is_none_or(|end| time < end)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Default)]
+pub enum PriceMode {
+    /// Tokens cost the phase's `prices`.
+    #[default]
+    Fixed,
+    /// The price falls from `start_price` to the phase's single native price.
+    DutchAuction(DutchAuction),
+    /// The price rises from the phase's single native price as tokens are minted or requested.
+    BondingCurve(BondingCurve),
+}
+
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct DutchAuction {
+    pub start_price: Uint128,
+    /// Amount the price drops by every `step_interval` seconds after the phase starts.
+    pub step: Uint128,
+    pub step_interval: u64,
+    /// Lets buyers claim back what they paid above the clearing price once the auction settles.
+    pub rebate: bool,
+}
+
+impl DutchAuction {
+    /// Price at `time` in an auction starting at `start_time`, never below `floor`.
+    pub fn price_at(&self, floor: Uint128, start_time: u64, time: u64) -> Uint128 {
+        let steps = time.saturating_sub(start_time) / self.step_interval;
+        let drop = self
+            .step
+            .checked_mul(Uint128::from(steps))
+            .unwrap_or(Uint128::MAX);
+        self.start_price.saturating_sub(drop).max(floor)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BondingCurve {
+    /// Each token costs `increment` more than the one before.
+    Linear { increment: Uint128 },
+    /// Each token costs `growth_bps` basis points more than the one before.
+    Exponential { growth_bps: u32 },
+    /// Flat prices by supply, ordered by `from_supply`.
+    Steps { steps: Vec<PriceStep> },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct PriceStep {
+    /// Tokens minted or requested before this price applies.
+    pub from_supply: u64,
+    pub price: Uint128,
+}
+
+impl BondingCurve {
+    /// Price of the token after `supply` already minted or requested, starting from `base`.
+    pub fn price_at(&self, base: Uint128, supply: u64) -> StdResult<Uint128> {
+        let overflow = || StdError::generic_err("Bonding curve price overflow");
+        match self {
+            BondingCurve::Linear { increment } => increment
+                .checked_mul(Uint128::from(supply))
+                .and_then(|increase| base.checked_add(increase))
+                .map_err(|_| overflow()),
+            BondingCurve::Exponential { growth_bps } => {
+                let exponent = u32::try_from(supply).map_err(|_| overflow())?;
+                let growth = Decimal::from_ratio(10_000 + u128::from(*growth_bps), 10_000u128)
+                    .checked_pow(exponent)
+                    .map_err(|_| overflow())?;
+                base.checked_mul_floor(growth).map_err(|_| overflow())
+            }
+            BondingCurve::Steps { steps } => Ok(steps
+                .iter()
+                .rev()
+                .find(|step| step.from_supply <= supply)
+                .map_or(base, |step| step.price)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct Royalty {
+    pub payment_address: String,
+    /// Percentage of each sale price, at most 100.
+    pub percentage: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct PlatformFee {
+    /// Receives the fee as soon as a mint is paid.
+    pub collector: Addr,
+    /// Share of each payment in basis points.
+    pub bps: u32,
+    /// Charged per token on top of the share when paying in one of these denoms.
+    #[serde(default)]
+    pub flat_fees: Vec<Coin>,
+    /// Charged per token on top of the share when paying with one of these cw20 tokens.
+    #[serde(default)]
+    pub cw20_flat_fees: Vec<Cw20Coin>,
+}
+
+impl PlatformFee {
+    /// Fee on `paid` for `quantity` tokens, never more than was paid.
+    pub fn fee_for(&self, paid: &Payment, quantity: u32) -> StdResult<Payment> {
+        let overflow = || StdError::generic_err("Platform fee overflow");
+        let flat = match paid {
+            Payment::Native(coin) => self
+                .flat_fees
+                .iter()
+                .find(|fee| fee.denom == coin.denom)
+                .map(|fee| fee.amount),
+            Payment::Cw20(coin) => self
+                .cw20_flat_fees
+                .iter()
+                .find(|fee| fee.address == coin.address.as_str())
+                .map(|fee| fee.amount),
+        };
+        let flat = flat
+            .unwrap_or_default()
+            .checked_mul(Uint128::from(quantity))
+            .map_err(|_| overflow())?;
+        let fee = paid
+            .amount()
+            .multiply_ratio(self.bps, 10_000u32)
+            .checked_add(flat)
+            .map_err(|_| overflow())?;
+        Ok(paid.with_amount(fee.min(paid.amount())))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct PendingOwner {
+    pub new_owner: Addr,
+    /// Block time after which the proposal can no longer be accepted.
+    pub expiry: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct Payee {
+    pub address: Addr,
+    /// Share of each withdrawal in basis points. All shares add up to 10000.
+    pub share_bps: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema, Default)]
+pub struct AuctionBid {
+    pub quantity: u32,
+    /// What the buyer paid, platform fee included.
+    pub paid: Uint128,
+}
+
+/// Key that signs `MintVoucher`s off-chain.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct VoucherSigner {
+    pub algorithm: SignatureAlgorithm,
+    /// Compressed or uncompressed secp256k1 key, or 32 byte ed25519 key.
+    pub pubkey: Binary,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureAlgorithm {
+    Secp256k1,
+    Ed25519,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Allowlist {
+    /// The addresses added with `ExecuteMsg::Whitelist`.
+    Whitelist,
+    /// Addresses proven against a hex-encoded sha256 merkle root. See `merkle::leaf`.
+    Merkle { root: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MintPhase {
+    NotStarted,
+    /// The phase with this id is open.
+    Active { id: u32 },
+    /// Between two scheduled phases.
+    BetweenPhases,
+    SoldOut,
+    Closed,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenRequestStatus {
+    Pending,
+    Fulfilled { token_id: String },
+    Refunded,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct TokenRequest {
+    /// Address that paid for the request.
+    pub requester: Addr,
+    /// Address the token will be minted to.
+    pub recipient: Addr,
+    pub paid: Payment,
+    pub phase_id: u32,
+    pub requested_at: u64,
+    pub expires_at: u64,
+    pub status: TokenRequestStatus,
+}
+
+impl TokenRequest {
+    pub fn save(&self, storage: &mut dyn Storage, index: u64) -> StdResult<()> {
+        token_requests(storage).save(&index.to_be_bytes(), self)
     }
 
-    pub fn state_load<S: ReadonlyStorage>(storage: &S) -> StdResult<Config> {
-        read_state(storage).load()
+    pub fn load(storage: &dyn Storage, index: u64) -> StdResult<TokenRequest> {
+        read_token_requests(storage).load(&index.to_be_bytes())
     }
 }
 
+/// Funds taken for a mint, in a native denom or a cw20 token.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Payment {
+    Native(Coin),
+    Cw20(Cw20CoinVerified),
+}
+
+impl Payment {
+    pub fn amount(&self) -> Uint128 {
+        match self {
+            Payment::Native(coin) => coin.amount,
+            Payment::Cw20(coin) => coin.amount,
+        }
+    }
+
+    /// Storage key identifying the denom or token.
+    pub fn key(&self) -> Vec<u8> {
+        match self {
+            Payment::Native(coin) => [b"native/", coin.denom.as_bytes()].concat(),
+            Payment::Cw20(coin) => [b"cw20/", coin.address.as_bytes()].concat(),
+        }
+    }
+
+    /// The same denom or token with a different amount.
+    pub fn with_amount(&self, amount: Uint128) -> Payment {
+        match self {
+            Payment::Native(coin) => Payment::Native(Coin {
+                denom: coin.denom.clone(),
+                amount,
+            }),
+            Payment::Cw20(coin) => Payment::Cw20(Cw20CoinVerified {
+                address: coin.address.clone(),
+                amount,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Payment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Payment::Native(coin) => write!(f, "{}", coin),
+            Payment::Cw20(coin) => write!(f, "{}{}", coin.amount, coin.address),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct WhitelistEntry {
+    pub added_at: u64,
+    /// Overrides the phase's per-address cap for this address.
+    pub mint_allocation: Option<u32>,
+    /// Overrides the phase's first native price for this address, in that price's denom.
+    pub price: Option<Uint128>,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Whitelist {
     // Define your Whitelist struct fields here
     pub addresses: Vec<Addr>,
 }
 
+impl Default for Whitelist {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Whitelist {
     pub fn new() -> Self {
         Whitelist {
@@ -91,12 +725,10 @@ impl Whitelist {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use cosmwasm_std::testing::MockStorage;
 
     #[test]
     fn test_whitelist() {
-        let mut storage = MockStorage::new();
-        let mut whitelist = whitelist(&mut storage);
+        let mut whitelist = Whitelist::new();
 
         let addr1 = Addr::unchecked("addr1");
         let addr2 = Addr::unchecked("addr2");
@@ -107,4 +739,26 @@ mod tests {
         assert!(whitelist.is_whitelisted(&addr1));
         assert!(!whitelist.is_whitelisted(&addr2));
     }
+
+    #[test]
+    fn test_bonding_curves() {
+        let base = Uint128::new(1000);
+
+        let linear = BondingCurve::Linear { increment: Uint128::new(10) };
+        assert_eq!(linear.price_at(base, 0).unwrap(), Uint128::new(1000));
+        assert_eq!(linear.price_at(base, 3).unwrap(), Uint128::new(1030));
+
+        let exponential = BondingCurve::Exponential { growth_bps: 1000 };
+        assert_eq!(exponential.price_at(base, 2).unwrap(), Uint128::new(1210));
+
+        let steps = BondingCurve::Steps {
+            steps: vec![
+                PriceStep { from_supply: 10, price: Uint128::new(2000) },
+                PriceStep { from_supply: 20, price: Uint128::new(3000) },
+            ],
+        };
+        assert_eq!(steps.price_at(base, 9).unwrap(), Uint128::new(1000));
+        assert_eq!(steps.price_at(base, 10).unwrap(), Uint128::new(2000));
+        assert_eq!(steps.price_at(base, 25).unwrap(), Uint128::new(3000));
+    }
 }